@@ -2,11 +2,12 @@ use std::cell::OnceCell;
 use std::env;
 use std::fs::{self, File};
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 use memmap2::Mmap;
 use typst::diag::StrResult;
 use typst::eval::Bytes;
-use typst::font::{Font, FontBook, FontInfo, FontVariant};
+use typst::font::{Font, FontBook, FontInfo, FontVariant, FontVariation};
 use walkdir::WalkDir;
 
 use crate::args::FontsCommand;
@@ -44,6 +45,10 @@ pub struct FontSlot {
     /// The index of the font in its collection. Zero if the path does not point
     /// to a collection.
     index: u32,
+    /// The variation axis values for a named instance of a variable font.
+    variations: Vec<FontVariation>,
+    /// The lazily loaded file, shared by all slots with the same path.
+    data: Rc<OnceCell<Option<Bytes>>>,
     /// The lazily loaded font.
     font: OnceCell<Option<Font>>,
 }
@@ -53,8 +58,12 @@ impl FontSlot {
     pub fn get(&self) -> Option<Font> {
         self.font
             .get_or_init(|| {
-                let data = fs::read(&self.path).ok()?.into();
+                let data = self
+                    .data
+                    .get_or_init(|| fs::read(&self.path).ok().map(Bytes::from))
+                    .clone()?;
                 Font::new(data, self.index)
+                    .map(|font| font.with_variations(&self.variations))
             })
             .clone()
     }
@@ -83,11 +92,13 @@ impl FontSearcher {
     fn add_embedded(&mut self) {
         let mut process = |bytes: &'static [u8]| {
             let buffer = Bytes::from_static(bytes);
-            for (i, font) in Font::iter(buffer).enumerate() {
+            for font in Font::iter(buffer) {
                 self.book.push(font.info().clone());
                 self.fonts.push(FontSlot {
                     path: PathBuf::new(),
-                    index: i as u32,
+                    index: font.index(),
+                    variations: font.variations().to_vec(),
+                    data: Rc::default(),
                     font: OnceCell::from(Some(font)),
                 });
             }
@@ -169,11 +180,15 @@ impl FontSearcher {
     fn search_file(&mut self, path: &Path) {
         if let Ok(file) = File::open(path) {
             if let Ok(mmap) = unsafe { Mmap::map(&file) } {
-                for (i, info) in FontInfo::iter(&mmap).enumerate() {
+                let data = Rc::new(OnceCell::new());
+                for (index, info) in FontInfo::iter_instances(&mmap) {
+                    let variations = info.variations.clone();
                     self.book.push(info);
                     self.fonts.push(FontSlot {
                         path: path.into(),
-                        index: i as u32,
+                        index,
+                        variations,
+                        data: data.clone(),
                         font: OnceCell::new(),
                     });
                 }
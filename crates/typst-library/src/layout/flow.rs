@@ -1,10 +1,13 @@
 use std::mem;
 
+use super::page::first_baseline;
 use super::{
-    AlignElem, BlockElem, ColbreakElem, ColumnsElem, ParElem, PlaceElem, Spacing, VElem,
+    AlignElem, BlockElem, ColbreakElem, ColumnsElem, Exclusion, PageElem, ParElem,
+    ParLine, PlaceElem, Spacing, VElem,
 };
-use crate::meta::{FootnoteElem, FootnoteEntry};
+use crate::meta::{FootnoteElem, FootnoteEntry, FootnotePlacement};
 use crate::prelude::*;
+use crate::text::TextElem;
 use crate::visualize::{
     CircleElem, EllipseElem, ImageElem, LineElem, PathElem, PolygonElem, RectElem,
     SquareElem,
@@ -103,6 +106,10 @@ struct FlowLayouter<'a> {
     items: Vec<FlowItem>,
     /// A queue of floats.
     pending_floats: Vec<FlowItem>,
+    /// Areas of the current region that paragraphs flow around.
+    exclusions: Vec<Exclusion>,
+    /// The distance between the lines of the baseline grid, if any.
+    grid: Option<Abs>,
     /// Whether we have any footnotes in the current region.
     has_footnotes: bool,
     /// Footnote configuration.
@@ -125,6 +132,9 @@ enum FlowItem {
     Absolute(Abs, bool),
     /// Fractional spacing between other items.
     Fractional(Fr),
+    /// Spacing that moves the baseline of the next frame onto the baseline
+    /// grid.
+    Pad(Abs),
     /// A frame for a layouted block, how to align it, whether it sticks to the
     /// item after it (for orphan prevention), and whether it is movable
     /// (to keep it together with its footnotes).
@@ -146,7 +156,7 @@ impl FlowItem {
     /// The inherent height of the item.
     fn height(&self) -> Abs {
         match self {
-            Self::Absolute(v, _) => *v,
+            Self::Absolute(v, _) | Self::Pad(v) => *v,
             Self::Fractional(_) | Self::Placed { .. } => Abs::zero(),
             Self::Frame { frame, .. } | Self::Footnote(frame) => frame.height(),
         }
@@ -171,6 +181,9 @@ impl<'a> FlowLayouter<'a> {
             last_was_par: false,
             items: vec![],
             pending_floats: vec![],
+            exclusions: vec![],
+            grid: PageElem::baseline_grid_in(styles)
+                .filter(|grid| root && *grid > Abs::zero()),
             has_footnotes: false,
             footnote_config: FootnoteConfig {
                 separator: FootnoteEntry::separator_in(styles),
@@ -212,38 +225,125 @@ impl<'a> FlowLayouter<'a> {
         let aligns = AlignElem::alignment_in(styles).resolve(styles);
         let leading = ParElem::leading_in(styles);
         let consecutive = self.last_was_par;
+
+        // Make the exclusions relative to the paragraph's top and cut them off
+        // at the region's end since lines in the next region are unaffected.
+        let offset = Point::with_y(self.cursor());
+        let remaining = self.regions.size.y;
+        let exclusions: Vec<_> = self
+            .exclusions
+            .iter()
+            .map(|exclusion| {
+                let pos = exclusion.pos - offset;
+                let mut size = exclusion.size;
+                size.y.set_min(remaining - pos.y);
+                Exclusion { pos, size }
+            })
+            .filter(|exclusion| {
+                exclusion.size.y > Abs::zero()
+                    && exclusion.pos.y + exclusion.size.y > Abs::zero()
+            })
+            .collect();
+
+        // The columns of vertical text extend over the remaining height.
+        let mut base = self.regions.base();
+        if TextElem::dir_in(styles) == Dir::TTB {
+            base.y = self.regions.size.y;
+        }
+
         let lines = par
-            .layout(vt, styles, consecutive, self.regions.base(), self.regions.expand.x)?
+            .layout(vt, styles, consecutive, base, self.regions.expand.x, &exclusions)?
             .into_frames();
 
         let mut sticky = self.items.len();
         for (i, item) in self.items.iter().enumerate().rev() {
             match *item {
-                FlowItem::Absolute(_, _) => {}
+                FlowItem::Absolute(_, _) | FlowItem::Pad(_) => {}
                 FlowItem::Frame { sticky: true, .. } => sticky = i,
                 _ => break,
             }
         }
 
-        if let Some(first) = lines.first() {
-            if !self.regions.size.y.fits(first.height()) && !self.regions.in_last() {
-                let carry: Vec<_> = self.items.drain(sticky..).collect();
-                self.finish_region(vt)?;
-                for item in carry {
-                    self.layout_item(vt, item)?;
-                }
+        // Group the lines into chunks that should not be split across regions
+        // to prevent orphans and widows.
+        let costs = ParElem::costs_in(styles);
+        let orphans =
+            if costs.orphan().is_zero() { 1 } else { ParElem::orphans_in(styles).get() };
+        let widows =
+            if costs.widow().is_zero() { 1 } else { ParElem::widows_in(styles).get() };
+        // Empty lines are neither orphans nor widows, so the paragraph may
+        // break next to them.
+        let len = lines.len();
+        let breakable: Vec<_> = (0..len)
+            .map(|i| {
+                (i >= orphans || lines[i].is_empty())
+                    && (len - i >= widows || (i > 0 && lines[i - 1].is_empty()))
+            })
+            .collect();
+
+        let mut chunks: Vec<Vec<Frame>> = vec![];
+        for (i, frame) in lines.into_iter().enumerate() {
+            match chunks.last_mut() {
+                Some(chunk) if !breakable[i] => chunk.push(frame),
+                _ => chunks.push(vec![frame]),
             }
         }
 
-        for (i, frame) in lines.into_iter().enumerate() {
-            if i > 0 {
-                self.layout_item(vt, FlowItem::Absolute(leading, true))?;
+        let count = chunks.len();
+        for (i, chunk) in chunks.into_iter().enumerate() {
+            let height = self.chunk_height(&chunk, leading, i > 0);
+
+            // If the chunk doesn't fit, we either move it to the next region
+            // or split it and accept the orphan or widow. Moving leaves the
+            // rest of the region empty, so it only happens if the empty
+            // fraction of the region is cheaper than the orphan or widow.
+            // Single lines are always moved. The first chunk carries sticky
+            // blocks like headings with it.
+            if !self.regions.size.y.fits(height) && !self.regions.in_last() {
+                let mut cost = Ratio::zero();
+                if i == 0 {
+                    cost = cost.max(costs.orphan());
+                }
+                if i + 1 == count {
+                    cost = cost.max(costs.widow());
+                }
+
+                let waste = self.regions.size.y / self.regions.full;
+                if chunk.len() == 1 || waste < cost.get().min(1.0) {
+                    let carry: Vec<_> = if i == 0 {
+                        self.items.drain(sticky..).collect()
+                    } else {
+                        vec![]
+                    };
+                    self.finish_region(vt)?;
+                    for item in carry {
+                        // Grid spacing is determined anew in the next region.
+                        if !matches!(item, FlowItem::Pad(_)) {
+                            self.layout_item(vt, item)?;
+                        }
+                    }
+                }
             }
 
-            self.layout_item(
-                vt,
-                FlowItem::Frame { frame, aligns, sticky: false, movable: true },
-            )?;
+            // A chunk that fits is placed as a single frame so that its lines
+            // also move together with their footnotes.
+            let height = self.chunk_height(&chunk, leading, i > 0);
+            let frames = if chunk.len() > 1 && self.regions.size.y.fits(height) {
+                vec![self.merge_chunk(chunk, leading, i > 0)]
+            } else {
+                chunk
+            };
+
+            for (j, frame) in frames.into_iter().enumerate() {
+                if i > 0 || j > 0 {
+                    self.layout_item(vt, FlowItem::Absolute(leading, true))?;
+                }
+
+                self.layout_item(
+                    vt,
+                    FlowItem::Frame { frame, aligns, sticky: false, movable: true },
+                )?;
+            }
         }
 
         self.last_was_par = true;
@@ -283,6 +383,24 @@ impl<'a> FlowLayouter<'a> {
         });
         let y_align = alignment.map(|align| align.y.resolve(styles));
         let frame = placed.layout(vt, styles, self.regions)?.into_frame();
+
+        // Reserve the area of the placed content so that subsequent
+        // paragraphs flow around it.
+        if placed.wrap(styles) {
+            let size = frame.size();
+            let x = x_align.position(self.initial.x - size.x);
+            let y = match y_align {
+                Smart::Custom(Some(align)) => align.position(self.initial.y - size.y),
+                _ => self.cursor(),
+            };
+            let pos = Point::new(x, y)
+                + delta.zip(self.initial).map(|(d, s)| d.relative_to(s)).to_point();
+            self.exclusions.push(Exclusion {
+                pos: pos - Point::splat(clearance),
+                size: size + Size::splat(clearance * 2.0),
+            });
+        }
+
         let item = FlowItem::Placed { frame, x_align, y_align, delta, float, clearance };
         self.layout_item(vt, item)
     }
@@ -361,10 +479,21 @@ impl<'a> FlowLayouter<'a> {
                 self.regions.size.y -= v
             }
             FlowItem::Fractional(_) => {}
+            FlowItem::Pad(v) => self.regions.size.y -= v,
             FlowItem::Frame { ref frame, movable, .. } => {
+                // Move the frame's first baseline onto the grid. If the frame
+                // doesn't fit with the extra spacing, it goes to the next
+                // region.
                 let height = frame.height();
-                if !self.regions.size.y.fits(height) && !self.regions.in_last() {
+                let mut pad = self.grid_pad(frame);
+                if !self.regions.size.y.fits(pad + height) && !self.regions.in_last() {
                     self.finish_region(vt)?;
+                    pad = self.grid_pad(frame);
+                }
+
+                if pad > Abs::zero() {
+                    self.regions.size.y -= pad;
+                    self.items.push(FlowItem::Pad(pad));
                 }
 
                 self.regions.size.y -= height;
@@ -374,7 +503,17 @@ impl<'a> FlowLayouter<'a> {
                     self.items.push(item);
                     if !self.handle_footnotes(vt, &mut notes, true, false)? {
                         let item = self.items.pop();
+                        if matches!(self.items.last(), Some(FlowItem::Pad(_))) {
+                            self.items.pop();
+                        }
                         self.finish_region(vt)?;
+                        if let Some(FlowItem::Frame { frame, .. }) = &item {
+                            let pad = self.grid_pad(frame);
+                            if pad > Abs::zero() {
+                                self.regions.size.y -= pad;
+                                self.items.push(FlowItem::Pad(pad));
+                            }
+                        }
                         self.items.extend(item);
                         self.regions.size.y -= height;
                         self.handle_footnotes(vt, &mut notes, true, true)?;
@@ -409,8 +548,12 @@ impl<'a> FlowLayouter<'a> {
                 }
 
                 // Add some clearance so that the float doesn't touch the main
-                // content.
+                // content. With a baseline grid, round the float's extent up
+                // to keep the main content on the grid.
                 frame.size_mut().y += clearance;
+                if let Some(grid) = self.grid {
+                    frame.size_mut().y = round_to_grid(frame.height(), grid);
+                }
                 if *y_align == Smart::Custom(Some(Align::Bottom)) {
                     frame.translate(Point::with_y(clearance));
                 }
@@ -431,6 +574,88 @@ impl<'a> FlowLayouter<'a> {
         Ok(())
     }
 
+    /// The distance from the region's top to the current position.
+    fn cursor(&self) -> Abs {
+        if self.initial.y.is_finite() {
+            self.initial.y - self.regions.size.y
+        } else {
+            // The remaining space of an infinite region stays infinite, so
+            // the placed items are measured instead.
+            self.items
+                .iter()
+                .filter(|item| !matches!(item, FlowItem::Footnote(_)))
+                .map(FlowItem::height)
+                .sum()
+        }
+    }
+
+    /// The spacing needed before a frame at the current position to move its
+    /// first baseline onto the grid.
+    fn grid_pad(&self, frame: &Frame) -> Abs {
+        self.grid_pad_at(self.cursor(), frame)
+    }
+
+    /// The spacing needed before a frame whose top is at `top` to move its
+    /// first baseline onto the grid. Frames without text need none.
+    fn grid_pad_at(&self, top: Abs, frame: &Frame) -> Abs {
+        let Some(grid) = self.grid else { return Abs::zero() };
+        let baseline = if frame.has_baseline() {
+            frame.baseline()
+        } else {
+            let Some(baseline) = first_baseline(frame) else { return Abs::zero() };
+            baseline
+        };
+        let baseline = top + baseline;
+        (round_to_grid(baseline, grid) - baseline).max(Abs::zero())
+    }
+
+    /// The height of a chunk of paragraph lines at the current position,
+    /// including the spacing that moves them onto the grid. If `lead` is
+    /// true, the chunk follows other lines of its paragraph and the leading
+    /// before it is included in the height.
+    fn chunk_height(&self, chunk: &[Frame], leading: Abs, lead: bool) -> Abs {
+        let start = self.cursor();
+        let mut top = start;
+        if lead {
+            top += leading;
+        }
+
+        for (j, line) in chunk.iter().enumerate() {
+            if j > 0 {
+                top += leading;
+            }
+            top += self.grid_pad_at(top, line) + line.height();
+        }
+
+        top - start
+    }
+
+    /// Merge a chunk of paragraph lines into one frame. The lines are spaced
+    /// like in [`chunk_height`](Self::chunk_height).
+    fn merge_chunk(&self, chunk: Vec<Frame>, leading: Abs, lead: bool) -> Frame {
+        let mut top = self.cursor();
+        if lead {
+            top += leading;
+        }
+
+        let mut lines = chunk.into_iter();
+        let mut merged = lines.next().unwrap();
+        top += self.grid_pad_at(top, &merged);
+
+        let start = top;
+        top += merged.height();
+        for line in lines {
+            top += leading;
+            top += self.grid_pad_at(top, &line);
+            let pos = Point::with_y(top - start);
+            top += line.height();
+            merged.push_frame(pos, line);
+        }
+
+        merged.size_mut().y = top - start;
+        merged
+    }
+
     /// Finish the frame for one region.
     fn finish_region(&mut self, vt: &mut Vt) -> SourceResult<()> {
         // Trim weak spacing.
@@ -451,7 +676,7 @@ impl<'a> FlowLayouter<'a> {
         let mut first_footnote = true;
         for item in &self.items {
             match item {
-                FlowItem::Absolute(v, _) => used.y += *v,
+                FlowItem::Absolute(v, _) | FlowItem::Pad(v) => used.y += *v,
                 FlowItem::Fractional(v) => fr += *v,
                 FlowItem::Frame { frame, .. } => {
                     used.y += frame.height();
@@ -495,7 +720,7 @@ impl<'a> FlowLayouter<'a> {
         // Place all frames.
         for item in self.items.drain(..) {
             match item {
-                FlowItem::Absolute(v, _) => {
+                FlowItem::Absolute(v, _) | FlowItem::Pad(v) => {
                     offset += v;
                 }
                 FlowItem::Fractional(v) => {
@@ -554,6 +779,7 @@ impl<'a> FlowLayouter<'a> {
         self.regions.next();
         self.initial = self.regions.size;
         self.has_footnotes = false;
+        self.exclusions.clear();
 
         // Try to place floats.
         for item in mem::take(&mut self.pending_floats) {
@@ -617,9 +843,12 @@ impl FlowLayouter<'_> {
                 self.layout_footnote_separator(vt)?;
             }
 
+            // The lines of footnote entries are not numbered: They sit below
+            // the page's text and have their own numbers.
             self.regions.size.y -= self.footnote_config.gap;
             let frames = FootnoteEntry::new(notes[k].clone())
                 .pack()
+                .styled(ParLine::set_numbering(None))
                 .layout(vt, self.styles, self.regions.with_root(false))?
                 .into_frames();
 
@@ -685,6 +914,17 @@ impl FlowLayouter<'_> {
     }
 }
 
+/// Round a length up to the next multiple of the grid distance.
+fn round_to_grid(length: Abs, grid: Abs) -> Abs {
+    // Tolerate rounding errors so that lines already on the grid stay put.
+    let steps = length / grid;
+    if (steps - steps.round()).abs() < 1e-6 {
+        grid * steps.round()
+    } else {
+        grid * steps.ceil()
+    }
+}
+
 /// Finds all footnotes in the frame.
 #[tracing::instrument(skip_all)]
 fn find_footnotes(notes: &mut Vec<FootnoteElem>, frame: &Frame) {
@@ -695,6 +935,9 @@ fn find_footnotes(notes: &mut Vec<FootnoteElem>, frame: &Frame) {
                 if !notes.iter().any(|note| note.0.location() == content.location()) =>
             {
                 let Some(footnote) = content.to::<FootnoteElem>() else { continue };
+                if footnote.placement(StyleChain::default()) == FootnotePlacement::End {
+                    continue;
+                }
                 notes.push(footnote.clone());
             }
             _ => {}
@@ -1,8 +1,10 @@
 use std::ptr;
 use std::str::FromStr;
 
-use super::{AlignElem, ColumnsElem};
-use crate::meta::{Counter, CounterKey, Numbering};
+use super::{AlignElem, ColumnsElem, LineNumberSide, LineNumberingScope, ParLineMarker};
+use crate::meta::{
+    Counter, CounterKey, Numbering, PageMarks, PagemarkElem, SidenoteMarker,
+};
 use crate::prelude::*;
 use crate::text::TextElem;
 
@@ -88,6 +90,35 @@ pub struct PageElem {
     #[default(false)]
     pub flipped: bool,
 
+    /// How much the page is rotated clockwise for display.
+    ///
+    /// Must be a multiple of `{90deg}`. A page rotated by `{90deg}` or
+    /// `{270deg}` is laid out in the other orientation, including its header,
+    /// footer, and page number, and displayed that way. Unlike with
+    /// [`flipped`]($func/page.flipped), the physical page keeps its size: PDF
+    /// viewers rotate it for display and printers still feed it like the
+    /// surrounding pages. Other exporters output the page as it is displayed.
+    ///
+    /// This is useful for a wide table in an otherwise portrait document.
+    /// Passing a body to the `page` function keeps the rotation local, so the
+    /// following content continues on unrotated pages.
+    ///
+    /// ```example
+    /// #set page(width: 100pt, height: 140pt, numbering: "1")
+    /// Portrait text.
+    ///
+    /// #page(rotation: 90deg)[
+    ///   #table(
+    ///     columns: 4,
+    ///     [A], [B], [C], [D],
+    ///     [1], [2], [3], [4],
+    ///   )
+    /// ]
+    ///
+    /// Portrait again.
+    /// ```
+    pub rotation: Angle,
+
     /// The page's margins.
     ///
     /// - `{auto}`: The margins are set automatically to 2.5/21 times the smaller
@@ -155,6 +186,30 @@ pub struct PageElem {
     #[default(NonZeroUsize::ONE)]
     pub columns: NonZeroUsize,
 
+    /// The distance between the lines of the page's baseline grid.
+    ///
+    /// When set, the baselines of paragraph lines are moved down onto the
+    /// nearest multiple of this distance, measured from the top of the page's
+    /// (or column's) content area. Headings and other blocks are moved such
+    /// that their first baseline is on the grid, so spacing between blocks
+    /// doesn't shift the text off the grid. The spacing after floating figures
+    /// is rounded up to the grid as well. This way, the lines of adjacent
+    /// columns line up even when headings or equations interrupt the text. The
+    /// grid distance typically matches the distance between two lines of body
+    /// text.
+    ///
+    /// ```example
+    /// #set page(columns: 2, height: 100pt, baseline-grid: 12pt)
+    /// #set par(leading: 12pt - 0.7em)
+    /// = A Heading
+    /// #lorem(10)
+    ///
+    /// $ a^2 + b^2 = c^2 $
+    /// #lorem(10)
+    /// ```
+    #[resolve]
+    pub baseline_grid: Option<Length>,
+
     /// The page's background color.
     ///
     /// This instructs the printer to color the complete page with the given
@@ -302,12 +357,19 @@ impl PageElem {
     /// while we post-process the pages in this function. This function returns
     /// a fragment consisting of multiple frames, one per output page of this
     /// page run.
+    ///
+    /// The `lines` are the number of paragraph lines that were numbered on
+    /// previous pages and the `marks` are the latest [marks]($func/mark) for
+    /// each key on previous pages. Both are also mutated while post-processing
+    /// the pages.
     #[tracing::instrument(skip_all)]
     pub fn layout(
         &self,
         vt: &mut Vt,
         styles: StyleChain,
         mut number: NonZeroUsize,
+        lines: &mut usize,
+        marks: &mut Vec<(Str, Content)>,
     ) -> SourceResult<Fragment> {
         tracing::info!("Page layout");
 
@@ -320,6 +382,17 @@ impl PageElem {
             std::mem::swap(&mut size.x, &mut size.y);
         }
 
+        // Lay a page that is rotated by a quarter turn out in the other
+        // orientation.
+        let rotation = self.rotation(styles);
+        let quarters = rotation.to_deg() / 90.0;
+        if quarters.fract() != 0.0 {
+            bail!(self.span(), "page rotation must be a multiple of 90deg");
+        }
+        if quarters.rem_euclid(2.0) == 1.0 {
+            std::mem::swap(&mut size.x, &mut size.y);
+        }
+
         let mut min = width.min(height);
         if !min.is_finite() {
             min = Paper::A4.width();
@@ -386,8 +459,15 @@ impl PageElem {
             Size::zero(),
         );
 
+        // Side notes that didn't fit onto their page and the size of the
+        // extra page they continue on after the last one.
+        let mut notes = vec![];
+        let blank = area.map(Abs::is_finite).select(area, Size::zero());
+
         // Post-process pages.
-        for frame in frames.iter_mut() {
+        let mut i = 0;
+        while i < frames.len() {
+            let frame = &mut frames[i];
             tracing::info!("Layouting page #{number}");
 
             // The padded width of the page's content without margins.
@@ -405,6 +485,25 @@ impl PageElem {
             frame.set_size(frame.size() + margin.sum_by_axis());
             frame.translate(Point::new(margin.left, margin.top));
             frame.push(Point::zero(), numbering_meta.clone());
+            if !rotation.is_zero() {
+                let meta = Meta::PageRotation(rotation);
+                frame.push(Point::zero(), FrameItem::Meta(meta, Size::zero()));
+            }
+
+            // Number the paragraph lines. The inside margin is on the left
+            // for odd left-bound and even right-bound pages.
+            let inside_left = (binding == Binding::Left) == (number.get() % 2 == 1);
+            let area = (margin.left, margin.left + pw);
+            number_lines(vt, styles, frame, area, inside_left, lines)?;
+
+            // Place the side notes into the outer margin. On one-sided pages,
+            // that's the one opposite to the binding.
+            let outside_left =
+                if two_sided { !inside_left } else { binding == Binding::Right };
+            place_sidenotes(vt, styles, frame, margin, outside_left, &mut notes)?;
+
+            // Collect the page's marks for its header and footer.
+            let page_marks = PageMarks::collect(frame, marks);
 
             // The page size with margins.
             let size = frame.size();
@@ -441,6 +540,7 @@ impl PageElem {
                 let sub = content
                     .clone()
                     .styled(AlignElem::set_alignment(align))
+                    .styled(PagemarkElem::set_marks(page_marks.clone()))
                     .layout(vt, styles, pod)?
                     .into_frame();
 
@@ -456,12 +556,176 @@ impl PageElem {
             }
 
             number = number.saturating_add(1);
+
+            // Side notes that don't fit onto the last page continue on an
+            // extra page.
+            i += 1;
+            if i == frames.len() && !notes.is_empty() {
+                frames.push(Frame::new(blank));
+            }
         }
 
         Ok(Fragment::frames(frames))
     }
 }
 
+/// Place numbers next to the marked paragraph lines of a page.
+///
+/// The `area` is the horizontal extent of the page's content area. The `count`
+/// is the number of lines numbered on previous pages.
+fn number_lines(
+    vt: &mut Vt,
+    styles: StyleChain,
+    frame: &mut Frame,
+    area: (Abs, Abs),
+    inside_left: bool,
+    count: &mut usize,
+) -> SourceResult<()> {
+    let mut markers: Vec<(Point, ParLineMarker)> = vec![];
+    find_markers(&mut markers, frame, Transform::identity());
+
+    // Lines that share a baseline also share a number. The sort is stable, so
+    // lines at the same height stay in layout order.
+    markers.sort_by_key(|(pos, _)| pos.y);
+
+    let mut prev: Option<Abs> = None;
+    let mut on_page = 0;
+    for (pos, marker) in markers {
+        if prev.map_or(false, |y| y.approx_eq(pos.y)) {
+            continue;
+        }
+
+        prev = Some(pos.y);
+        on_page += 1;
+        *count += 1;
+
+        let n = match marker.scope() {
+            LineNumberingScope::Document => *count,
+            LineNumberingScope::Page => on_page,
+        };
+
+        if n % marker.interval().get() != 0 {
+            continue;
+        }
+
+        let left = match marker.side() {
+            LineNumberSide::Left => true,
+            LineNumberSide::Right => false,
+            LineNumberSide::Inside => inside_left,
+            LineNumberSide::Outside => !inside_left,
+        };
+
+        let pod = Regions::one(Size::splat(Abs::inf()), Axes::splat(false));
+        let sub = marker
+            .numbering()
+            .apply_vt(vt, &[n])?
+            .display()
+            .layout(vt, styles, pod)?
+            .into_frame();
+
+        let clearance = marker.clearance().abs;
+        let x = if left { area.0 - clearance - sub.width() } else { area.1 + clearance };
+
+        frame.push_frame(Point::new(x, pos.y - sub.baseline()), sub);
+    }
+
+    Ok(())
+}
+
+/// Place the side notes of a page into its outer margin.
+///
+/// The notes are aligned with the line that references them and stacked
+/// downwards if they would overlap. Notes that don't fit are carried over to
+/// the next page in `pending`. The first note is always placed, so that every
+/// page makes progress.
+fn place_sidenotes(
+    vt: &mut Vt,
+    styles: StyleChain,
+    frame: &mut Frame,
+    margin: Sides<Abs>,
+    left: bool,
+    pending: &mut Vec<SidenoteMarker>,
+) -> SourceResult<()> {
+    let mut markers: Vec<(Point, SidenoteMarker)> = vec![];
+    find_markers(&mut markers, frame, Transform::identity());
+    markers.sort_by_key(|(pos, _)| pos.y);
+
+    // Carried-over notes go to the top of the margin.
+    let carried = std::mem::take(pending)
+        .into_iter()
+        .map(|marker| (Point::with_y(margin.top), marker));
+
+    let size = frame.size();
+    let bottom = size.y - margin.bottom;
+    let mut cursor = margin.top;
+    let mut placed = false;
+    for (pos, marker) in carried.chain(markers).collect::<Vec<_>>() {
+        // Once a note spills, all later ones follow to keep their order.
+        if !pending.is_empty() {
+            pending.push(marker);
+            continue;
+        }
+
+        let clearance = marker.clearance().abs;
+        let width = if left { margin.left } else { margin.right } - 2.0 * clearance;
+        let pod = Regions::one(
+            Size::new(width.max(Abs::zero()), Abs::inf()),
+            Axes::splat(false),
+        );
+        let sub = marker.body().layout(vt, styles, pod)?.into_frame();
+
+        // Align the note's first baseline with the referencing line.
+        let y = (pos.y - first_baseline(&sub).unwrap_or_default()).max(cursor);
+        if placed && y + sub.height() > bottom {
+            pending.push(marker);
+            continue;
+        }
+
+        let x = if left { clearance } else { size.x - margin.right + clearance };
+        cursor = y + sub.height() + marker.gap().abs;
+        placed = true;
+        frame.push_frame(Point::new(x, y), sub);
+    }
+
+    Ok(())
+}
+
+/// The baseline of the first line in a frame, if any.
+pub(super) fn first_baseline(frame: &Frame) -> Option<Abs> {
+    frame.items().find_map(|(pos, item)| match item {
+        FrameItem::Group(group) if group.frame.has_baseline() => {
+            Some(pos.y + group.frame.baseline())
+        }
+        FrameItem::Group(group) => first_baseline(&group.frame).map(|y| pos.y + y),
+        FrameItem::Text(_) => Some(pos.y),
+        _ => None,
+    })
+}
+
+/// Find all markers of the given kind in a frame along with their positions.
+fn find_markers<T: Element + Clone>(
+    markers: &mut Vec<(Point, T)>,
+    frame: &Frame,
+    ts: Transform,
+) {
+    for (pos, item) in frame.items() {
+        match item {
+            FrameItem::Group(group) => {
+                let ts = ts
+                    .pre_concat(Transform::translate(pos.x, pos.y))
+                    .pre_concat(group.transform);
+                find_markers(markers, &group.frame, ts);
+            }
+            FrameItem::Meta(Meta::Marker(content), _) => {
+                if let Some(marker) = content.to::<T>() {
+                    markers.push((pos.transform(ts), marker.clone()));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 /// Specification of the page's margins.
 #[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct Margin {
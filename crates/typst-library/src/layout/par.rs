@@ -1,3 +1,5 @@
+use std::cmp::Ordering;
+
 use icu_properties::{maps::CodePointMapData, LineBreak};
 use icu_provider::AsDeserializingBufferProvider;
 use icu_provider_adapters::fork::ForkByKeyProvider;
@@ -11,11 +13,13 @@ use unicode_script::{Script, UnicodeScript};
 
 use super::{BoxElem, HElem, Sizing, Spacing};
 use crate::layout::AlignElem;
-use crate::math::EquationElem;
+use crate::math::{EquationElem, MathParItem};
+use crate::meta::Numbering;
 use crate::prelude::*;
 use crate::text::{
-    is_gb_style, shape, LinebreakElem, Quoter, Quotes, ShapedText, SmartQuoteElem,
-    SpaceElem, TextElem,
+    add_cjk_latin_spacing, compress_punctuation, hyphenate, is_gb_style, shape,
+    LinebreakElem, Quoter, Quotes, RubyGroup, ShapedGlyph, ShapedText, SmartQuoteElem,
+    SpaceElem, TextDir, TextElem, INTER_CLUSTER_STRETCH,
 };
 
 /// Arranges text, spacing and inline-level elements into a paragraph.
@@ -47,6 +51,10 @@ use crate::text::{
 /// Display: Paragraph
 /// Category: layout
 #[element(Construct)]
+#[scope(
+    scope.define("line", ParLine::func());
+    scope
+)]
 pub struct ParElem {
     /// The spacing between lines.
     #[resolve]
@@ -59,6 +67,10 @@ pub struct ParElem {
     /// property hyphenate]($func/text.hyphenate) is set to `{auto}` and the
     /// current language is known.
     ///
+    /// Arabic text is justified by elongating words with kashidas where the
+    /// font supports it. Only the remaining space is distributed between the
+    /// words.
+    ///
     /// Note that the current [alignment]($func/align) still has an effect on
     /// the placement of the last line except if it ends with a [justified line
     /// break]($func/linebreak.justify).
@@ -91,6 +103,105 @@ pub struct ParElem {
     #[default]
     pub linebreaks: Smart<Linebreaks>,
 
+    /// The costs of various layout decisions.
+    ///
+    /// Costs are given relative to their default, so `{200%}` makes a
+    /// decision twice as expensive and `{0%}` makes it free. The dictionary
+    /// can contain the following keys:
+    ///
+    /// - `hyphenation`: The cost of ending a line with a hyphen. Only
+    ///   considered by the optimized line breaker.
+    /// - `runt`: The cost of ending a paragraph with a line that holds just a
+    ///   single word. Only considered by the optimized line breaker.
+    /// - `widow`: The cost of leaving fewer than [`widows`]($func/par.widows)
+    ///   lines of a paragraph alone at the top of a region. A widow is avoided
+    ///   by moving more lines to the next region if the fraction of the
+    ///   current region that then stays empty is smaller than this cost. With
+    ///   the default of `{100%}`, widows are thus always avoided and with
+    ///   `{0%}` they are always allowed.
+    /// - `orphan`: The cost of leaving fewer than
+    ///   [`orphans`]($func/par.orphans) lines of a paragraph alone at the
+    ///   bottom of a region. It is weighed against the empty space in the same
+    ///   way as the widow cost.
+    ///
+    /// ```example
+    /// #set page(width: 190pt)
+    /// #set par(justify: true)
+    /// #set par(costs: (hyphenation: 0%))
+    /// Some texts are frustratingly
+    /// challenging to break in a
+    /// visually pleasing way.
+    ///
+    /// #set par(costs: (hyphenation: 500%))
+    /// Some texts are frustratingly
+    /// challenging to break in a
+    /// visually pleasing way.
+    /// ```
+    #[fold]
+    pub costs: Costs,
+
+    /// The minimum number of lines of a paragraph that must stay together at
+    /// the bottom of a region when the paragraph breaks across regions.
+    ///
+    /// If the paragraph is too short to satisfy both this and the
+    /// [`widows`]($func/par.widows) requirement, it is kept together as a
+    /// whole. Headings and other sticky blocks stay with these lines.
+    ///
+    /// This sets how many lines count as an orphan while the `orphan` entry
+    /// of the [`costs`]($func/par.costs) decides whether it is worth leaving
+    /// space empty to avoid one. With an orphan cost of `{0%}`, this setting
+    /// has no effect.
+    ///
+    /// ```example
+    /// #set page(height: 90pt)
+    /// #set par(orphans: 3)
+    /// #v(28pt)
+    /// #lorem(30)
+    /// ```
+    #[default(NonZeroUsize::new(2).unwrap())]
+    pub orphans: NonZeroUsize,
+
+    /// The minimum number of lines of a paragraph that must stay together at
+    /// the top of a region when the paragraph breaks across regions.
+    ///
+    /// Like the [`orphans`]($func/par.orphans), this is weighed by the
+    /// `widow` entry of the [`costs`]($func/par.costs).
+    #[default(NonZeroUsize::new(2).unwrap())]
+    pub widows: NonZeroUsize,
+
+    /// How many lines more or fewer than the optimum the paragraph should
+    /// have.
+    ///
+    /// A positive looseness makes the optimized line breaker choose the
+    /// feasible layout with the line count closest to the optimal count plus
+    /// the looseness, for example to pull a single word from the next page
+    /// back into the paragraph. A negative looseness tightens the paragraph.
+    /// The simple line breaker ignores this property.
+    ///
+    /// ```example
+    /// #set page(width: 190pt)
+    /// #set par(justify: true, looseness: 1)
+    /// This paragraph is set one line
+    /// longer than it would need to be.
+    /// ```
+    pub looseness: i64,
+
+    /// How much the spaces of a line may shrink at most, relative to their
+    /// natural shrinkability.
+    ///
+    /// The optimized line breaker considers lines that would need to shrink
+    /// more than this as overfull.
+    #[parse(non_negative(args, "max-shrink")?)]
+    #[default(Ratio::one())]
+    pub max_shrink: Ratio,
+
+    /// How much the spaces of a justified line may stretch, relative to their
+    /// natural stretchability, before extra space is distributed between all
+    /// justifiable characters.
+    #[parse(non_negative(args, "max-stretch")?)]
+    #[default(Ratio::one())]
+    pub max_stretch: Ratio,
+
     /// The indent the first line of a paragraph should have.
     ///
     /// Only the first line of a consecutive paragraph will be indented (not
@@ -143,6 +254,7 @@ impl ParElem {
         consecutive: bool,
         region: Size,
         expand: bool,
+        exclusions: &[Exclusion],
     ) -> SourceResult<Fragment> {
         #[comemo::memoize]
         #[allow(clippy::too_many_arguments)]
@@ -157,6 +269,7 @@ impl ParElem {
             consecutive: bool,
             region: Size,
             expand: bool,
+            exclusions: &[Exclusion],
         ) -> SourceResult<Fragment> {
             let mut locator = Locator::chained(locator);
             let mut vt = Vt {
@@ -168,19 +281,73 @@ impl ParElem {
             };
             let children = par.children();
 
+            // Vertical text is laid out in horizontal lines as long as the
+            // region is high, which are then turned into columns.
+            let vertical = TextElem::dir_in(styles) == Dir::TTB;
+            let mut local = Styles::new();
+            let styles = if vertical {
+                local.set(TextElem::set_dir(TextDir(Smart::Custom(Dir::LTR))));
+                local.set(TextElem::set_vertical(true));
+                styles.chain(&local)
+            } else {
+                styles
+            };
+
             // Collect all text into one string for BiDi analysis.
-            let (text, segments, spans) = collect(&children, &styles, consecutive)?;
+            let line_region =
+                if vertical { Size::new(region.y, region.x) } else { region };
+            let (text, segments, spans) =
+                collect(&mut vt, &children, &styles, line_region, consecutive)?;
 
             // Perform BiDi analysis and then prepare paragraph layout by building a
             // representation on which we can do line breaking without layouting
             // each and every line from scratch.
-            let p = prepare(&mut vt, &children, &text, segments, spans, styles, region)?;
+            let p =
+                prepare(&mut vt, &children, &text, segments, spans, styles, line_region)?;
+
+            if vertical {
+                let lines = linebreak(&vt, &p, region.y - p.hang, None);
+
+                // In a region of infinite height, the columns are as long as
+                // the longest line.
+                let height = if region.y.is_finite() {
+                    region.y
+                } else {
+                    p.hang + lines.iter().map(|line| line.width).max().unwrap_or_default()
+                };
+
+                return finalize_vertical(
+                    &mut vt,
+                    &p,
+                    &lines,
+                    Size::new(region.x, height),
+                );
+            }
+
+            // Determine the space available to each line if the paragraph
+            // flows around exclusions.
+            let mut wrap = (!exclusions.is_empty())
+                .then(|| Wrap::new(&vt, &p, region.x, exclusions));
 
             // Break the paragraph into lines.
-            let lines = linebreak(&vt, &p, region.x - p.hang);
+            let mut lines = linebreak(&vt, &p, region.x - p.hang, wrap.as_ref());
+
+            // The line positions next to exclusions are only estimated before
+            // line breaking. Refine them with the real line heights until the
+            // lines' spans stay the same.
+            if let Some(wrap) = &mut wrap {
+                for _ in 0..MAX_WRAP_PASSES {
+                    let measured = wrap.measure(&vt, &lines);
+                    if (0..lines.len()).all(|k| measured.span(k) == wrap.span(k)) {
+                        break;
+                    }
+                    *wrap = measured;
+                    lines = linebreak(&vt, &p, region.x - p.hang, Some(wrap));
+                }
+            }
 
             // Stack the lines into one frame per region.
-            finalize(&mut vt, &p, &lines, region, expand)
+            finalize(&mut vt, &p, &lines, region, expand, wrap.as_ref())
         }
 
         let fragment = cached(
@@ -194,6 +361,7 @@ impl ParElem {
             consecutive,
             region,
             expand,
+            exclusions,
         )?;
 
         vt.locator.visit_frames(&fragment);
@@ -201,6 +369,16 @@ impl ParElem {
     }
 }
 
+/// An area that the lines of a paragraph flow around, relative to the
+/// paragraph's top-left corner.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Exclusion {
+    /// The top-left corner of the area.
+    pub pos: Point,
+    /// The size of the area.
+    pub size: Size,
+}
+
 /// How to determine line breaks in a paragraph.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Cast)]
 pub enum Linebreaks {
@@ -213,6 +391,249 @@ pub enum Linebreaks {
     Optimized,
 }
 
+/// Costs for various layout decisions.
+///
+/// Each cost is a ratio of the default cost and missing costs are inherited
+/// from outer set rules.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Costs {
+    hyphenation: Option<Ratio>,
+    runt: Option<Ratio>,
+    widow: Option<Ratio>,
+    orphan: Option<Ratio>,
+}
+
+impl Costs {
+    /// The cost of a hyphenated line break.
+    pub fn hyphenation(&self) -> Ratio {
+        self.hyphenation.unwrap_or(Ratio::one())
+    }
+
+    /// The cost of a paragraph ending with a single-word line.
+    pub fn runt(&self) -> Ratio {
+        self.runt.unwrap_or(Ratio::one())
+    }
+
+    /// The cost of a widow.
+    pub fn widow(&self) -> Ratio {
+        self.widow.unwrap_or(Ratio::one())
+    }
+
+    /// The cost of an orphan.
+    pub fn orphan(&self) -> Ratio {
+        self.orphan.unwrap_or(Ratio::one())
+    }
+}
+
+impl Fold for Costs {
+    type Output = Self;
+
+    fn fold(self, outer: Self::Output) -> Self::Output {
+        Self {
+            hyphenation: self.hyphenation.or(outer.hyphenation),
+            runt: self.runt.or(outer.runt),
+            widow: self.widow.or(outer.widow),
+            orphan: self.orphan.or(outer.orphan),
+        }
+    }
+}
+
+cast! {
+    Costs,
+    self => {
+        let mut dict = Dict::new();
+        let mut handle = |key: &str, cost: Option<Ratio>| {
+            if let Some(cost) = cost {
+                dict.insert(key.into(), cost.into_value());
+            }
+        };
+
+        handle("hyphenation", self.hyphenation);
+        handle("runt", self.runt);
+        handle("widow", self.widow);
+        handle("orphan", self.orphan);
+
+        Value::Dict(dict)
+    },
+    mut dict: Dict => {
+        let mut take = |key| {
+            let cost = dict.take(key).ok().map(Value::cast::<Ratio>).transpose()?;
+            if cost.map_or(false, |cost| cost.get() < 0.0) {
+                bail!("costs must not be negative");
+            }
+            StrResult::Ok(cost)
+        };
+        let hyphenation = take("hyphenation")?;
+        let runt = take("runt")?;
+        let widow = take("widow")?;
+        let orphan = take("orphan")?;
+        dict.finish(&["hyphenation", "runt", "widow", "orphan"])?;
+        Self { hyphenation, runt, widow, orphan }
+    },
+}
+
+/// Parse a named ratio argument that must not be negative.
+fn non_negative(args: &mut Args, name: &str) -> SourceResult<Option<Ratio>> {
+    let Some(Spanned { v, span }) = args.named::<Spanned<Ratio>>(name)? else {
+        return Ok(None);
+    };
+    if v.get() < 0.0 {
+        bail!(span, "ratio must not be negative");
+    }
+    Ok(Some(v))
+}
+
+/// Configures the numbering of paragraph lines.
+///
+/// When a numbering is set, every line laid out by a [paragraph]($func/par)
+/// is numbered. The numbers are placed into the page margin next to the line's
+/// baseline and do not affect the layout of the text itself. Lines that share
+/// a baseline, like lines in adjacent columns, share a number.
+///
+/// This function is not intended to be called directly. Instead, it is used
+/// in set rules to configure line numbering.
+///
+/// ## Example { #example }
+/// ```example
+/// #set page(margin: (left: 32pt))
+/// #set par.line(numbering: "1", interval: 2)
+///
+/// Roses are red. \
+/// Violets are blue. \
+/// Typst is there for you.
+/// ```
+///
+/// Display: Paragraph Line
+/// Category: layout
+#[element]
+pub struct ParLine {
+    /// How to [number]($func/numbering) the lines.
+    ///
+    /// If `{none}`, lines are not numbered.
+    ///
+    /// ```example
+    /// #set page(margin: (left: 32pt))
+    /// #set par.line(numbering: "i")
+    ///
+    /// First line \
+    /// Second line
+    /// ```
+    pub numbering: Option<Numbering>,
+
+    /// Only every n-th line is labelled with its number. The lines in between
+    /// are still counted.
+    #[default(NonZeroUsize::ONE)]
+    pub interval: NonZeroUsize,
+
+    /// In which margin to place the line numbers.
+    ///
+    /// - `left`: In the left margin.
+    /// - `right`: In the right margin.
+    /// - `{"inside"}`: In the inner margin (where the
+    ///   [binding]($func/page.binding) is).
+    /// - `{"outside"}`: In the outer margin (opposite to the binding).
+    ///
+    /// ```example
+    /// #set page(margin: (right: 32pt))
+    /// #set par.line(numbering: "1", side: right)
+    ///
+    /// Numbered on the right.
+    /// ```
+    #[default(LineNumberSide::Left)]
+    pub side: LineNumberSide,
+
+    /// The distance between a line number and the text area of the page.
+    #[resolve]
+    #[default(Em::new(1.0).into())]
+    pub clearance: Length,
+
+    /// Whether the line numbering runs through the whole document or restarts
+    /// on each page.
+    ///
+    /// ```example
+    /// #set page(height: 60pt, margin: (left: 32pt))
+    /// #set par.line(numbering: "1", scope: "page")
+    ///
+    /// First page \
+    /// continues \
+    /// and restarts \
+    /// on the next.
+    /// ```
+    #[default(LineNumberingScope::Document)]
+    pub scope: LineNumberingScope,
+}
+
+/// In which margin to place line numbers.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum LineNumberSide {
+    /// In the left margin.
+    Left,
+    /// In the right margin.
+    Right,
+    /// In the inner margin.
+    Inside,
+    /// In the outer margin.
+    Outside,
+}
+
+cast! {
+    LineNumberSide,
+    self => match self {
+        Self::Left => GenAlign::Specific(Align::Left).into_value(),
+        Self::Right => GenAlign::Specific(Align::Right).into_value(),
+        Self::Inside => "inside".into_value(),
+        Self::Outside => "outside".into_value(),
+    },
+    /// In the inner margin (where the binding is).
+    "inside" => Self::Inside,
+    /// In the outer margin (opposite to the binding).
+    "outside" => Self::Outside,
+    v: GenAlign => match v {
+        GenAlign::Specific(Align::Left) => Self::Left,
+        GenAlign::Specific(Align::Right) => Self::Right,
+        _ => bail!("must be `left`, `right`, `\"inside\"`, or `\"outside\"`"),
+    },
+}
+
+/// Over which range line numbers are counted.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Cast)]
+pub enum LineNumberingScope {
+    /// Lines are counted throughout the whole document.
+    Document,
+    /// The line count restarts on each page.
+    Page,
+}
+
+/// Marks the baseline of a numbered paragraph line in its frame.
+///
+/// The page layouter picks these markers up and places the line numbers into
+/// the margin.
+///
+/// Display: Paragraph Line Marker
+/// Category: special
+#[element]
+pub struct ParLineMarker {
+    /// How to number the line.
+    #[required]
+    pub numbering: Numbering,
+
+    /// Only every n-th line is labelled.
+    #[required]
+    pub interval: NonZeroUsize,
+
+    /// In which margin to place the number.
+    #[required]
+    pub side: LineNumberSide,
+
+    /// The distance between the number and the text area.
+    #[required]
+    pub clearance: Length,
+
+    /// Over which range the line is counted.
+    #[required]
+    pub scope: LineNumberingScope,
+}
+
 /// A paragraph break.
 ///
 /// This starts a new paragraph. Especially useful when used within code like
@@ -247,6 +668,14 @@ type Range = std::ops::Range<usize>;
 const SPACING_REPLACE: char = ' '; // Space
 const OBJ_REPLACE: char = '\u{FFFC}'; // Object Replacement Character
 
+/// The character by which a piece of an inline equation is replaced.
+fn math_replace(item: &MathParItem) -> char {
+    match item {
+        MathParItem::Space(_) => SPACING_REPLACE,
+        MathParItem::Frame(_) => OBJ_REPLACE,
+    }
+}
+
 /// A paragraph representation in which children are already layouted and text
 /// is already preshaped.
 ///
@@ -319,15 +748,16 @@ impl<'a> Preparation<'a> {
 }
 
 /// A segment of one or multiple collapsed children.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
 enum Segment<'a> {
     /// One or multiple collapsed text or text-equivalent children. Stores how
     /// long the segment is (in bytes of the full text string).
     Text(usize),
     /// Horizontal spacing between other segments.
     Spacing(Spacing),
-    /// A mathematical equation.
-    Equation(&'a EquationElem),
+    /// A mathematical equation, split into pieces between which lines may
+    /// break.
+    Equation(Vec<MathParItem>),
     /// A box with arbitrary content.
     Box(&'a BoxElem, bool),
     /// Metadata.
@@ -341,14 +771,17 @@ impl Segment<'_> {
             Self::Text(len) => len,
             Self::Spacing(_) => SPACING_REPLACE.len_utf8(),
             Self::Box(_, true) => SPACING_REPLACE.len_utf8(),
-            Self::Equation(_) | Self::Box(_, _) => OBJ_REPLACE.len_utf8(),
+            Self::Equation(ref items) => {
+                items.iter().map(math_replace).map(char::len_utf8).sum()
+            }
+            Self::Box(_, _) => OBJ_REPLACE.len_utf8(),
             Self::Meta => 0,
         }
     }
 }
 
 /// A prepared item in a paragraph layout.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 enum Item<'a> {
     /// A shaped text run with consistent style and direction.
     Text(ShapedText<'a>),
@@ -358,6 +791,9 @@ enum Item<'a> {
     Fractional(Fr, Option<(&'a BoxElem, StyleChain<'a>)>),
     /// Layouted inline-level content.
     Frame(Frame),
+    /// A layouted ruby group and how far its annotation may overhang the
+    /// adjacent text on each side.
+    Ruby(Frame, Abs),
     /// Metadata.
     Meta(Frame),
 }
@@ -383,7 +819,7 @@ impl<'a> Item<'a> {
         match self {
             Self::Text(shaped) => shaped.text.len(),
             Self::Absolute(_) | Self::Fractional(_, _) => SPACING_REPLACE.len_utf8(),
-            Self::Frame(_) => OBJ_REPLACE.len_utf8(),
+            Self::Frame(_) | Self::Ruby(_, _) => OBJ_REPLACE.len_utf8(),
             Self::Meta(_) => 0,
         }
     }
@@ -393,7 +829,7 @@ impl<'a> Item<'a> {
         match self {
             Self::Text(shaped) => shaped.width,
             Self::Absolute(v) => *v,
-            Self::Frame(frame) => frame.width(),
+            Self::Frame(frame) | Self::Ruby(frame, _) => frame.width(),
             Self::Fractional(_, _) | Self::Meta(_) => Abs::zero(),
         }
     }
@@ -438,6 +874,7 @@ impl SpanMapper {
 /// first and last one since they may be broken apart by the start or end of the
 /// line, respectively. But even those can partially reuse previous results when
 /// the break index is safe-to-break per rustybuzz.
+#[derive(Clone)]
 struct Line<'a> {
     /// Bidi information about the paragraph.
     bidi: &'a BidiInfo<'a>,
@@ -498,12 +935,12 @@ impl<'a> Line<'a> {
         for shaped in self.items().filter_map(Item::text) {
             count += shaped.justifiables();
         }
-        // CJK character at line end should not be adjusted.
+        // CJK character or cluster at line end should not be adjusted.
         if self
             .items()
             .last()
             .and_then(Item::text)
-            .map(|s| s.cjk_justifiable_at_last())
+            .map(|s| s.justifiable_at_last())
             .unwrap_or(false)
         {
             count -= 1;
@@ -512,9 +949,23 @@ impl<'a> Line<'a> {
         count
     }
 
+    /// How many kashidas can be inserted into Arabic text in the line.
+    fn kashidas(&self) -> usize {
+        self.items().filter_map(Item::text).map(|s| s.kashidas()).sum()
+    }
+
     /// How much can the line stretch
     fn stretchability(&self) -> Abs {
-        self.items().filter_map(Item::text).map(|s| s.stretchability()).sum()
+        let mut stretch: Abs =
+            self.items().filter_map(Item::text).map(|s| s.stretchability()).sum();
+        // Space between clusters at line end is not visible, so it doesn't
+        // count.
+        if let Some(text) = self.items().last().and_then(Item::text) {
+            if text.glyphs.last().map_or(false, |g| g.inter_cluster) {
+                stretch -= INTER_CLUSTER_STRETCH.at(text.size);
+            }
+        }
+        stretch
     }
 
     /// How much can the line shrink
@@ -522,6 +973,29 @@ impl<'a> Line<'a> {
         self.items().filter_map(Item::text).map(|s| s.shrinkability()).sum()
     }
 
+    /// How much can the line grow or shrink through font expansion.
+    fn expandability(&self) -> Abs {
+        self.items().filter_map(Item::text).map(|s| s.expandability()).sum()
+    }
+
+    /// The height of the line once it is committed.
+    fn height(&self, vt: &Vt) -> Abs {
+        let mut top = Abs::zero();
+        let mut bottom = Abs::zero();
+        for item in self.items() {
+            let (t, b) = match item {
+                Item::Text(shaped) => shaped.measure(vt),
+                Item::Frame(frame) | Item::Ruby(frame, _) => {
+                    (frame.baseline(), frame.height() - frame.baseline())
+                }
+                _ => continue,
+            };
+            top.set_max(t);
+            bottom.set_max(b);
+        }
+        top + bottom
+    }
+
     /// The sum of fractions in the line.
     fn fr(&self) -> Fr {
         self.items()
@@ -537,8 +1011,10 @@ impl<'a> Line<'a> {
 /// string-level preprocessing like case transformations.
 #[allow(clippy::type_complexity)]
 fn collect<'a>(
+    vt: &mut Vt,
     children: &'a [Content],
     styles: &'a StyleChain<'a>,
+    region: Size,
     consecutive: bool,
 ) -> SourceResult<(String, Vec<(Segment<'a>, StyleChain<'a>)>, SpanMapper)> {
     let mut full = String::new();
@@ -551,7 +1027,7 @@ fn collect<'a>(
     if !first_line_indent.is_zero()
         && consecutive
         && AlignElem::alignment_in(*styles).x.resolve(*styles)
-            == TextElem::dir_in(*styles).start().into()
+            == GenAlign::Start.resolve(*styles)
     {
         full.push(SPACING_REPLACE);
         segments.push((Segment::Spacing(first_line_indent.into()), *styles));
@@ -629,8 +1105,10 @@ fn collect<'a>(
             }
             Segment::Text(full.len() - prev)
         } else if let Some(elem) = child.to::<EquationElem>() {
-            full.push(OBJ_REPLACE);
-            Segment::Equation(elem)
+            let pod = Regions::one(region, Axes::splat(false));
+            let items = elem.layout_inline(vt, styles, pod)?;
+            full.extend(items.iter().map(math_replace));
+            Segment::Equation(items)
         } else if let Some(elem) = child.to::<BoxElem>() {
             let frac = elem.width(styles).is_fractional();
             full.push(if frac { SPACING_REPLACE } else { OBJ_REPLACE });
@@ -648,7 +1126,7 @@ fn collect<'a>(
         spans.push(segment.len(), child.span());
 
         if let (Some((Segment::Text(last_len), last_styles)), Segment::Text(len)) =
-            (segments.last_mut(), segment)
+            (segments.last_mut(), &segment)
         {
             if *last_styles == styles {
                 *last_len += len;
@@ -701,15 +1179,29 @@ fn prepare<'a>(
                     items.push(Item::Fractional(v, None));
                 }
             },
-            Segment::Equation(equation) => {
-                let pod = Regions::one(region, Axes::splat(false));
-                let mut frame = equation.layout(vt, styles, pod)?.into_frame();
-                frame.translate(Point::with_y(TextElem::baseline_in(styles)));
-                items.push(Item::Frame(frame));
+            Segment::Equation(pieces) => {
+                for piece in pieces {
+                    match piece {
+                        MathParItem::Space(space) => {
+                            items.push(Item::Absolute(space));
+                        }
+                        MathParItem::Frame(mut frame) => {
+                            frame.translate(Point::with_y(TextElem::baseline_in(styles)));
+                            items.push(Item::Frame(frame));
+                        }
+                    }
+                }
             }
             Segment::Box(elem, _) => {
                 if let Sizing::Fr(v) = elem.width(styles) {
                     items.push(Item::Fractional(v, Some((elem, styles))));
+                } else if let Some(group) =
+                    elem.body(styles).and_then(|body| body.to::<RubyGroup>().cloned())
+                {
+                    let pod = Regions::one(region, Axes::splat(false));
+                    let (mut frame, overhang) = group.layout_inline(vt, styles, pod)?;
+                    frame.translate(Point::with_y(TextElem::baseline_in(styles)));
+                    items.push(Item::Ruby(frame, overhang));
                 } else {
                     let pod = Regions::one(region, Axes::splat(false));
                     let mut frame = elem.layout(vt, styles, pod)?.into_frame();
@@ -727,6 +1219,14 @@ fn prepare<'a>(
         cursor = end;
     }
 
+    adjust_text_boundaries(&mut items);
+
+    for item in &items {
+        if let Item::Text(shaped) = item {
+            shaped.warn_fallback(vt);
+        }
+    }
+
     Ok(Preparation {
         bidi,
         items,
@@ -795,6 +1295,72 @@ fn shape_range<'a>(
     process(cursor..range.end, prev_level);
 }
 
+/// Add CJK-Latin spacing and compress CJK punctuation between adjacent text
+/// items, which are shaped separately.
+fn adjust_text_boundaries(items: &mut [Item]) {
+    let mut prev = None;
+    for i in 0..items.len() {
+        match &items[i] {
+            Item::Text(_) => {}
+            Item::Meta(_) => continue,
+            _ => {
+                prev = None;
+                continue;
+            }
+        }
+
+        if let Some(j) = prev {
+            let (head, tail) = items.split_at_mut(i);
+            if let (Item::Text(a), Item::Text(b)) = (&mut head[j], &mut tail[0]) {
+                adjust_text_boundary(a, b);
+            }
+        }
+
+        prev = Some(i);
+    }
+}
+
+/// Add CJK-Latin spacing and compress CJK punctuation between the last glyph
+/// of `a` and the first glyph of `b`.
+fn adjust_text_boundary<'a>(a: &mut ShapedText<'a>, b: &mut ShapedText<'a>) {
+    if !a.dir.is_positive()
+        || !b.dir.is_positive()
+        || a.glyphs.is_empty()
+        || b.glyphs.is_empty()
+    {
+        return;
+    }
+
+    // Only clone the glyphs if one of the adjustments applies.
+    let (last, first) = (a.glyphs.last().unwrap(), b.glyphs.first().unwrap());
+    let spacing = TextElem::cjk_latin_spacing_in(a.styles)
+        && TextElem::cjk_latin_spacing_in(b.styles)
+        && ((last.is_cjk_script() && first.is_letter_or_number())
+            || (last.is_letter_or_number() && first.is_cjk_script()));
+    let compress = TextElem::compress_punctuation_in(a.styles)
+        && TextElem::compress_punctuation_in(b.styles)
+        && is_gb_style(a.lang, a.region)
+        && is_gb_style(b.lang, b.region)
+        && last.is_cjk_punctuation()
+        && first.is_cjk_punctuation();
+    if !spacing && !compress {
+        return;
+    }
+
+    let glyph = a.glyphs.to_mut().last_mut().unwrap();
+    let next = b.glyphs.to_mut().first_mut().unwrap();
+    if spacing {
+        add_cjk_latin_spacing(glyph, next);
+    }
+    if compress {
+        compress_punctuation(glyph, next);
+    }
+
+    for text in [a, b] {
+        text.width = text.glyphs.iter().map(|g| g.x_advance).sum::<Em>().at(text.size);
+    }
+}
+
 /// Whether this is not a specific script.
 fn is_generic_script(script: Script) -> bool {
     matches!(script, Script::Unknown | Script::Common | Script::Inherited)
@@ -821,7 +1387,12 @@ fn shared_get<T: PartialEq>(
 }
 
 /// Find suitable linebreaks.
-fn linebreak<'a>(vt: &Vt, p: &'a Preparation<'a>, width: Abs) -> Vec<Line<'a>> {
+fn linebreak<'a>(
+    vt: &Vt,
+    p: &'a Preparation<'a>,
+    width: Abs,
+    wrap: Option<&Wrap>,
+) -> Vec<Line<'a>> {
     let linebreaks = ParElem::linebreaks_in(p.styles).unwrap_or_else(|| {
         if ParElem::justify_in(p.styles) {
             Linebreaks::Optimized
@@ -830,16 +1401,30 @@ fn linebreak<'a>(vt: &Vt, p: &'a Preparation<'a>, width: Abs) -> Vec<Line<'a>> {
         }
     });
 
-    match linebreaks {
-        Linebreaks::Simple => linebreak_simple(vt, p, width),
-        Linebreaks::Optimized => linebreak_optimized(vt, p, width),
+    // Lines next to exclusions have varying widths.
+    match (linebreaks, wrap) {
+        (Linebreaks::Simple, None) => linebreak_simple(vt, p, |_| width),
+        (Linebreaks::Simple, Some(wrap)) => {
+            linebreak_simple(vt, p, |k| wrap.span(k).1 - p.hang)
+        }
+        (Linebreaks::Optimized, None) => linebreak_optimized(vt, p, |_| width, false),
+        (Linebreaks::Optimized, Some(wrap)) => {
+            linebreak_optimized(vt, p, |k| wrap.span(k).1 - p.hang, true)
+        }
     }
 }
 
 /// Perform line breaking in simple first-fit style. This means that we build
 /// lines greedily, always taking the longest possible line. This may lead to
 /// very unbalanced line, but is fast and simple.
-fn linebreak_simple<'a>(vt: &Vt, p: &'a Preparation<'a>, width: Abs) -> Vec<Line<'a>> {
+///
+/// The `width` determines the available width for the line with the given
+/// index.
+fn linebreak_simple<'a>(
+    vt: &Vt,
+    p: &'a Preparation<'a>,
+    width: impl Fn(usize) -> Abs,
+) -> Vec<Line<'a>> {
     let mut lines = vec![];
     let mut start = 0;
     let mut last = None;
@@ -851,7 +1436,7 @@ fn linebreak_simple<'a>(vt: &Vt, p: &'a Preparation<'a>, width: Abs) -> Vec<Line
         // If the line doesn't fit anymore, we push the last fitting attempt
         // into the stack and rebuild the line from the attempt's end. The
         // resulting line cannot be broken up further.
-        if !width.fits(attempt.width) {
+        if !width(lines.len()).fits(attempt.width) {
             if let Some((last_attempt, last_end)) = last.take() {
                 lines.push(last_attempt);
                 start = last_end;
@@ -862,7 +1447,7 @@ fn linebreak_simple<'a>(vt: &Vt, p: &'a Preparation<'a>, width: Abs) -> Vec<Line
         // Finish the current line if there is a mandatory line break (i.e.
         // due to "\n") or if the line doesn't fit horizontally already
         // since then no shorter line will be possible.
-        if mandatory || !width.fits(attempt.width) {
+        if mandatory || !width(lines.len()).fits(attempt.width) {
             lines.push(attempt);
             start = end;
             last = None;
@@ -895,132 +1480,269 @@ fn linebreak_simple<'a>(vt: &Vt, p: &'a Preparation<'a>, width: Abs) -> Vec<Line
 /// computed and stored in dynamic programming table) is minimal. The final
 /// result is simply the layout determined for the last breakpoint at the end of
 /// text.
-fn linebreak_optimized<'a>(vt: &Vt, p: &'a Preparation<'a>, width: Abs) -> Vec<Line<'a>> {
+fn linebreak_optimized<'a>(
+    vt: &Vt,
+    p: &'a Preparation<'a>,
+    width: impl Fn(usize) -> Abs,
+    varying: bool,
+) -> Vec<Line<'a>> {
     /// The cost of a line or paragraph layout.
     type Cost = f64;
 
     /// An entry in the dynamic programming table.
     struct Entry<'a> {
+        /// The index of the predecessor entry in the table.
         pred: usize,
         total: Cost,
+        /// The number of lines up to and including this one.
+        count: usize,
+        /// Whether none of the lines up to this one is overfull.
+        fits: bool,
         line: Line<'a>,
     }
 
+    /// Orders layouts from best to worst by their total cost. With lines of
+    /// varying width, layouts without overfull lines always come first, even
+    /// if stretching a lone word next to an exclusion made them infinitely
+    /// costly. Each layout is given by whether it fits and its total cost.
+    let rank = |a: (bool, Cost), b: (bool, Cost)| -> Ordering {
+        let fits = if varying { b.0.cmp(&a.0) } else { Ordering::Equal };
+        fits.then(a.1.total_cmp(&b.1))
+    };
+
     // Cost parameters.
     const HYPH_COST: Cost = 0.5;
     const RUNT_COST: Cost = 0.5;
     const CONSECUTIVE_DASH_COST: Cost = 300.0;
     const MAX_COST: Cost = 1_000_000.0;
-    const MIN_RATIO: f64 = -1.0;
 
-    // Dynamic programming table.
+    let costs = ParElem::costs_in(p.styles);
+    let hyph_cost = HYPH_COST * costs.hyphenation().get();
+    let runt_cost = RUNT_COST * costs.runt().get();
+    let max_shrink = ParElem::max_shrink_in(p.styles).get();
+    let max_stretch = ParElem::max_stretch_in(p.styles).get();
+
+    // With a non-zero looseness or lines of varying width, we keep the best
+    // layout for each line count at each breakpoint instead of just the
+    // overall best one. Only counts within the looseness of the best layout's
+    // count can matter in the end. With varying widths, a layout with one more
+    // or fewer lines can also lead to a better overall result.
+    let looseness = ParElem::looseness_in(p.styles);
+    let window = looseness.unsigned_abs() as usize + usize::from(varying);
+
+    // Dynamic programming table. The entries for each breakpoint are stored
+    // consecutively and `bounds` holds their index range.
     let mut active = 0;
     let mut table = vec![Entry {
         pred: 0,
         total: 0.0,
+        count: 0,
+        fits: true,
         line: line(vt, p, 0..0, false, false),
     }];
+    let mut bounds = vec![0..1];
+    let mut best: Vec<Entry> = vec![];
 
     let em = TextElem::size_in(p.styles);
 
     for (end, mandatory, hyphen) in breakpoints(p) {
-        let k = table.len();
+        let k = bounds.len();
         let eof = end == p.bidi.text.len();
-        let mut best: Option<Entry> = None;
 
         // Find the optimal predecessor.
-        for (i, pred) in table.iter_mut().enumerate().skip(active) {
+        for (i, range) in bounds.iter().enumerate().skip(active) {
             // Layout the line.
-            let start = pred.line.end;
+            let start = table[range.start].line.end;
             let attempt = line(vt, p, start..end, mandatory, hyphen);
 
-            // Determine how much the line's spaces would need to be stretched
-            // to make it the desired width.
-            let delta = width - attempt.width;
-            // Determine how much stretch are permitted.
-            let adjust = if delta >= Abs::zero() {
-                attempt.stretchability()
-            } else {
-                attempt.shrinkability()
+            // Determine the cost of the line if it has the given width. The
+            // second value is whether the line is overfull.
+            let line_cost = |width: Abs| {
+                // Determine how much the line's spaces would need to be
+                // stretched to make it the desired width.
+                let delta = width - attempt.width;
+                // Determine how much stretch are permitted.
+                let mut adjust = if delta >= Abs::zero() {
+                    attempt.stretchability()
+                } else {
+                    attempt.shrinkability()
+                };
+                // Font expansion adjusts justified lines in both directions,
+                // kashidas only lengthen them.
+                if p.justify {
+                    adjust += attempt.expandability();
+                    if delta >= Abs::zero() {
+                        adjust += em * attempt.kashidas() as f64;
+                    }
+                }
+                // Ideally, the ratio should between -max_shrink and
+                // max_stretch, but sometimes a value above max_stretch is
+                // possible, in which case the line is underfull.
+                let mut ratio = delta / adjust;
+                if ratio.is_nan() {
+                    // The line is not stretchable, but it just fits.
+                    // This often happens with monospace fonts and CJK texts.
+                    ratio = 0.0;
+                }
+                if ratio > max_stretch {
+                    // We should stretch the line above its stretchability. Now
+                    // calculate the extra amount.
+                    let extra_stretch =
+                        (delta - adjust * max_stretch) / attempt.justifiables() as f64;
+                    // Normalize the amount by half Em size.
+                    ratio = max_stretch + extra_stretch / (em / 2.0);
+                }
+
+                // Determine the cost of the line.
+                let min_ratio = if p.justify { -max_shrink } else { 0.0 };
+                let overfull = ratio < min_ratio;
+                let mut cost = if overfull {
+                    // The line is overfull. This is the case if
+                    // - justification is on, but we'd need to shrink too much
+                    // - justification is off and the line just doesn't fit
+                    MAX_COST
+                } else if mandatory || eof {
+                    // If ratio > 0, we need to stretch the line only when
+                    // justify is needed. If ratio < 0, we always need to
+                    // shrink the line.
+                    if (ratio > 0.0 && attempt.justify) || ratio < 0.0 {
+                        ratio.powi(3).abs()
+                    } else {
+                        0.0
+                    }
+                } else {
+                    // Normal line with cost of |ratio^3|.
+                    ratio.powi(3).abs()
+                };
+
+                // Penalize runts.
+                if k == i + 1 && eof {
+                    cost += runt_cost;
+                }
+
+                // Penalize hyphens.
+                if hyphen {
+                    cost += hyph_cost;
+                }
+
+                // In Knuth paper, cost = (1 + 100|r|^3 + p)^2 + a,
+                // where r is the ratio, p=50 is penaty, and a=3000 is
+                // consecutive penaty. We divide the whole formula by 10,
+                // resulting (0.01 + |r|^3 + p)^2 + a, where p=0.5 and a=300
+                ((0.01 + cost).powi(2), overfull)
             };
-            // Ideally, the ratio should between -1.0 and 1.0, but sometimes a value above 1.0
-            // is possible, in which case the line is underfull.
-            let mut ratio = delta / adjust;
-            if ratio.is_nan() {
-                // The line is not stretchable, but it just fits.
-                // This often happens with monospace fonts and CJK texts.
-                ratio = 0.0;
-            }
-            if ratio > 1.0 {
-                // We should stretch the line above its stretchability. Now calculate the extra amount.
-                let extra_stretch = (delta - adjust) / attempt.justifiables() as f64;
-                // Normalize the amount by half Em size.
-                ratio = 1.0 + extra_stretch / (em / 2.0);
+
+            // Find the predecessors for which this attempt improves on what we
+            // had before. Each predecessor variant has a distinct line count,
+            // so they never compete for the same slot. With a uniform width,
+            // all variants share the same line cost.
+            let uniform = (!varying).then(|| line_cost(width(0)));
+            let mut all_overfull = true;
+            let mut improvements = vec![];
+            for pred in range.clone() {
+                let entry = &table[pred];
+                let (mut cost, overfull) =
+                    uniform.unwrap_or_else(|| line_cost(width(entry.count)));
+                all_overfull &= overfull;
+
+                // Penalize two consecutive dashes (not necessarily hyphens) extra.
+                if attempt.dash && entry.line.dash {
+                    cost += CONSECUTIVE_DASH_COST;
+                }
+
+                // The total cost of this line and its chain of predecessors.
+                let total = entry.total + cost;
+                let count = entry.count + 1;
+                let fits = entry.fits && !overfull;
+
+                let slot = if window == 0 {
+                    (!best.is_empty()).then_some(0)
+                } else {
+                    best.iter().position(|entry| entry.count == count)
+                };
+
+                match slot {
+                    Some(slot)
+                        if rank((best[slot].fits, best[slot].total), (fits, total))
+                            .is_lt() => {}
+                    _ => improvements.push((slot, pred, total, count, fits)),
+                }
             }
 
-            // Determine the cost of the line.
-            let min_ratio = if p.justify { MIN_RATIO } else { 0.0 };
-            let mut cost = if ratio < min_ratio {
-                // The line is overfull. This is the case if
-                // - justification is on, but we'd need to shrink too much
-                // - justification is off and the line just doesn't fit
-                // Since any longer line will also be overfull, we can deactivate
-                // this breakpoint.
+            if all_overfull {
+                // Since any longer line will also be overfull, we can
+                // deactivate this breakpoint.
                 active = i + 1;
-                MAX_COST
             } else if mandatory || eof {
                 // This is a mandatory break and the line is not overfull, so
                 // all breakpoints before this one become inactive since no line
                 // can span above the mandatory break.
                 active = k;
-                // If ratio > 0, we need to stretch the line only when justify is needed.
-                // If ratio < 0, we always need to shrink the line.
-                if (ratio > 0.0 && attempt.justify) || ratio < 0.0 {
-                    ratio.powi(3).abs()
-                } else {
-                    0.0
-                }
-            } else {
-                // Normal line with cost of |ratio^3|.
-                ratio.powi(3).abs()
-            };
-
-            // Penalize runts.
-            if k == i + 1 && eof {
-                cost += RUNT_COST;
             }
 
-            // Penalize hyphens.
-            if hyphen {
-                cost += HYPH_COST;
-            }
-
-            // In Knuth paper, cost = (1 + 100|r|^3 + p)^2 + a,
-            // where r is the ratio, p=50 is penaty, and a=3000 is consecutive penaty.
-            // We divide the whole formula by 10, resulting (0.01 + |r|^3 + p)^2 + a,
-            // where p=0.5 and a=300
-            cost = (0.01 + cost).powi(2);
+            // If this attempt is better than what we had before, take it! The
+            // line is only cloned if it improves on more than one slot.
+            let mut attempt = Some(attempt);
+            let last = improvements.len().saturating_sub(1);
+            for (n, (slot, pred, total, count, fits)) in
+                improvements.into_iter().enumerate()
+            {
+                let line = if n == last {
+                    attempt.take().unwrap()
+                } else {
+                    attempt.clone().unwrap()
+                };
 
-            // Penalize two consecutive dashes (not necessarily hyphens) extra.
-            if attempt.dash && pred.line.dash {
-                cost += CONSECUTIVE_DASH_COST;
+                let entry = Entry { pred, total, count, fits, line };
+                match slot {
+                    Some(slot) => best[slot] = entry,
+                    None => best.push(entry),
+                }
             }
+        }
 
-            // The total cost of this line and its chain of predecessors.
-            let total = pred.total + cost;
-
-            // If this attempt is better than what we had before, take it!
-            if best.as_ref().map_or(true, |best| best.total >= total) {
-                best = Some(Entry { pred: i, total, line: attempt });
+        // Drop the variants whose line count is too far from the best one's.
+        if window != 0 {
+            if let Some(optimal) = best
+                .iter()
+                .min_by(|a, b| rank((a.fits, a.total), (b.fits, b.total)))
+                .map(|entry| entry.count)
+            {
+                best.retain(|entry| entry.count.abs_diff(optimal) <= window);
             }
         }
 
-        table.push(best.unwrap());
+        bounds.push(table.len()..table.len() + best.len());
+        table.append(&mut best);
     }
 
-    // Retrace the best path.
+    // Pick the layout with the best total cost, or, with a non-zero
+    // looseness, the fitting layout whose line count is closest to the
+    // optimal count plus the looseness.
+    let last = bounds.last().unwrap().clone();
+    let optimal = last
+        .clone()
+        .min_by(|&a, &b| {
+            rank((table[a].fits, table[a].total), (table[b].fits, table[b].total))
+        })
+        .unwrap();
+    let target = table[optimal].count as i64 + looseness;
+    let chosen = if looseness == 0 {
+        optimal
+    } else {
+        last.filter(|&j| table[j].fits)
+            .min_by(|&a, &b| {
+                let distance = |j: usize| (table[j].count as i64 - target).abs();
+                distance(a)
+                    .cmp(&distance(b))
+                    .then(table[a].total.total_cmp(&table[b].total))
+            })
+            .unwrap_or(optimal)
+    };
+
+    // Retrace the best path. Predecessors always come earlier in the table.
     let mut lines = vec![];
-    let mut idx = table.len() - 1;
+    let mut idx = chosen;
     while idx != 0 {
         table.truncate(idx + 1);
         let entry = table.pop().unwrap();
@@ -1039,7 +1761,8 @@ fn linebreak_optimized<'a>(vt: &Vt, p: &'a Preparation<'a>, width: Abs) -> Vec<L
 ///                --format blob --out library/assets/icudata.postcard --overwrite
 /// ```
 ///
-/// Install icu4x-datagen with `cargo install icu4x-datagen`.
+/// Install icu4x-datagen with `cargo install icu4x-datagen --version 1.2.*`
+/// to match the version of `icu_segmenter`.
 static ICU_DATA: &[u8] = include_bytes!("../../assets/icudata.postcard");
 
 /// Generated by the following command:
@@ -1105,8 +1828,8 @@ struct Breakpoints<'a> {
     p: &'a Preparation<'a>,
     /// The inner iterator over the unicode line break opportunities.
     linebreaks: LineBreakIteratorUtf8<'a, 'a>,
-    /// Iterator over syllables of the current word.
-    syllables: Option<hypher::Syllables<'a>>,
+    /// Iterator over the byte lengths of the current word's syllables.
+    syllables: Option<std::vec::IntoIter<usize>>,
     /// The current text offset.
     offset: usize,
     /// The trimmed end of the current word.
@@ -1123,7 +1846,7 @@ impl Iterator for Breakpoints<'_> {
     fn next(&mut self) -> Option<Self::Item> {
         // If we're currently in a hyphenated "word", process the next syllable.
         if let Some(syllable) = self.syllables.as_mut().and_then(Iterator::next) {
-            self.offset += syllable.len();
+            self.offset += syllable;
             if self.offset == self.suffix {
                 self.offset = self.end;
             }
@@ -1160,8 +1883,10 @@ impl Iterator for Breakpoints<'_> {
                 let trimmed = word.trim_end_matches(|c: char| !c.is_alphabetic());
                 if !trimmed.is_empty() {
                     self.suffix = self.offset + trimmed.len();
-                    self.syllables = Some(hypher::hyphenate(trimmed, lang));
-                    return self.next();
+                    if let Some(syllables) = self.syllables(trimmed, lang) {
+                        self.syllables = Some(syllables.into_iter());
+                        return self.next();
+                    }
                 }
             }
         }
@@ -1184,14 +1909,27 @@ impl Breakpoints<'_> {
     }
 
     /// The text language at the given offset.
-    fn lang(&self, offset: usize) -> Option<hypher::Lang> {
-        let lang = self.p.lang.or_else(|| {
+    fn lang(&self, offset: usize) -> Option<Lang> {
+        self.p.lang.or_else(|| {
             let shaped = self.p.find(offset)?.text()?;
             Some(TextElem::lang_in(shaped.styles))
-        })?;
+        })
+    }
+
+    /// The byte lengths of the syllables of the word at the current offset.
+    ///
+    /// User-provided exceptions and patterns take precedence over the
+    /// built-in ones.
+    fn syllables(&self, word: &str, lang: Lang) -> Option<Vec<usize>> {
+        if let Some(shaped) = self.p.find(self.offset).and_then(Item::text) {
+            if let Some(syllables) = hyphenate(word, lang, shaped.styles) {
+                return Some(syllables);
+            }
+        }
 
         let bytes = lang.as_str().as_bytes().try_into().ok()?;
-        hypher::Lang::from_iso(bytes)
+        let lang = hypher::Lang::from_iso(bytes)?;
+        Some(hypher::hyphenate(word, lang).map(str::len).collect())
     }
 }
 
@@ -1257,9 +1995,23 @@ fn line<'a>(
         // need the shaped empty string to make the line the appropriate
         // height. That is the case exactly if the string is empty and there
         // are no other items in the line.
-        if hyphen || start + shaped.text.len() > range.end || end_cjk_punct {
+        // CJK-Latin spacing at the line end is removed.
+        let end_spacing = shaped
+            .glyphs
+            .last()
+            .map_or(false, |glyph| glyph.cjk_latin_spacing.1 > Em::zero());
+
+        if hyphen || start + shaped.text.len() > range.end || end_cjk_punct || end_spacing
+        {
             if hyphen || start < range.end || before.is_empty() {
                 let mut reshaped = shaped.reshape(vt, &p.spans, start..range.end);
+                if let Some(glyph) = reshaped.glyphs.last() {
+                    if glyph.cjk_latin_spacing.1 > Em::zero() {
+                        let glyph = reshaped.glyphs.to_mut().last_mut().unwrap();
+                        let amount = glyph.trim_spacing_right();
+                        reshaped.width -= amount.at(reshaped.size);
+                    }
+                }
                 if hyphen || shy {
                     reshaped.push_hyphen(vt);
                 }
@@ -1291,8 +2043,14 @@ fn line<'a>(
         let base = expanded.start;
         let end = range.end.min(base + shaped.text.len());
 
+        // CJK-Latin spacing at the line start is removed.
+        let start_spacing = shaped
+            .glyphs
+            .first()
+            .map_or(false, |glyph| glyph.cjk_latin_spacing.0 > Em::zero());
+
         // Reshape if necessary.
-        if range.start + shaped.text.len() > end || start_cjk_punct {
+        if range.start + shaped.text.len() > end || start_cjk_punct || start_spacing {
             if range.start < end || start_cjk_punct {
                 let reshaped = shaped.reshape(vt, &p.spans, range.start..end);
                 width += reshaped.width;
@@ -1319,12 +2077,29 @@ fn line<'a>(
         }
     }
 
+    // Remove CJK-Latin spacing at the line start.
+    let reshaped = match first.as_mut() {
+        Some(first) => first.text_mut(),
+        None if inner.is_empty() => last.as_mut().and_then(Item::text_mut),
+        None => None,
+    };
+    if let Some(reshaped) = reshaped {
+        if let Some(glyph) = reshaped.glyphs.first() {
+            if glyph.cjk_latin_spacing.0 > Em::zero() {
+                let glyph = reshaped.glyphs.to_mut().first_mut().unwrap();
+                let amount_abs = glyph.trim_spacing_left().at(reshaped.size);
+                reshaped.width -= amount_abs;
+                width -= amount_abs;
+            }
+        }
+    }
+
     // Measure the inner items.
     for item in inner {
         width += item.width();
     }
 
-    Line {
+    let mut line = Line {
         bidi: &p.bidi,
         trimmed: range,
         end,
@@ -1334,7 +2109,29 @@ fn line<'a>(
         width,
         justify,
         dash,
-    }
+    };
+
+    // Ruby annotations overhang the adjacent text.
+    let items: Vec<_> = line.items().collect();
+    let overhang: Abs = (0..items.len())
+        .map(|i| {
+            let (left, right) = ruby_overhang(&items, i);
+            left + right
+        })
+        .sum();
+    line.width -= overhang;
+    line
+}
+
+/// How far the ruby annotation at index `i` of a line's items overhangs the
+/// adjacent items on its left and right. It only does so next to text.
+fn ruby_overhang(items: &[&Item], i: usize) -> (Abs, Abs) {
+    let Item::Ruby(_, amount) = items[i] else { return (Abs::zero(), Abs::zero()) };
+    let beside = |j: Option<usize>| match j.and_then(|j| items.get(j)) {
+        Some(Item::Text(_)) => *amount,
+        _ => Abs::zero(),
+    };
+    (beside(i.checked_sub(1)), beside(Some(i + 1)))
 }
 
 /// Combine layouted lines into one frame per region.
@@ -1344,11 +2141,13 @@ fn finalize(
     lines: &[Line],
     region: Size,
     expand: bool,
+    wrap: Option<&Wrap>,
 ) -> SourceResult<Fragment> {
     // Determine the paragraph's width: Full width of the region if we
-    // should expand or there's fractional spacing, fit-to-width otherwise.
+    // should expand, there's fractional spacing, or the lines flow around
+    // exclusions, fit-to-width otherwise.
     let width = if !region.x.is_finite()
-        || (!expand && lines.iter().all(|line| line.fr().is_zero()))
+        || (!expand && wrap.is_none() && lines.iter().all(|line| line.fr().is_zero()))
     {
         region
             .x
@@ -1360,34 +2159,181 @@ fn finalize(
     // Stack the lines into one frame per region.
     let mut frames: Vec<Frame> = lines
         .iter()
-        .map(|line| commit(vt, p, line, width, region.y))
+        .enumerate()
+        .map(|(k, line)| {
+            let Some(wrap) = wrap else {
+                return commit(vt, p, line, width, region.y);
+            };
+
+            // Commit the line to the span next to the exclusions.
+            let (offset, span) = wrap.span(k);
+            let mut frame = commit(vt, p, line, span, region.y)?;
+            frame.translate(Point::with_x(offset));
+            frame.size_mut().x = width;
+            Ok(frame)
+        })
         .collect::<SourceResult<_>>()?;
 
-    // Prevent orphans.
-    let leading = ParElem::leading_in(p.styles);
-    if frames.len() >= 2 && !frames[1].is_empty() {
-        let second = frames.remove(1);
-        let first = &mut frames[0];
-        merge(first, second, leading);
+    // Mark the lines for numbering.
+    if let Some(numbering) = ParLine::numbering_in(p.styles) {
+        let marker = ParLineMarker::new(
+            numbering,
+            ParLine::interval_in(p.styles),
+            ParLine::side_in(p.styles),
+            ParLine::clearance_in(p.styles).into(),
+            ParLine::scope_in(p.styles),
+        )
+        .pack();
+
+        for frame in &mut frames {
+            let pos = Point::with_y(frame.baseline());
+            frame.push(pos, FrameItem::Meta(Meta::Marker(marker.clone()), Size::zero()));
+        }
     }
 
-    // Prevent widows.
-    let len = frames.len();
-    if len >= 2 && !frames[len - 2].is_empty() {
-        let second = frames.pop().unwrap();
-        let first = frames.last_mut().unwrap();
-        merge(first, second, leading);
+    Ok(Fragment::frames(frames))
+}
+
+/// Turn the lines of a vertical paragraph into columns.
+///
+/// The lines were laid out as long as the region is high. They are rotated
+/// by a quarter turn clockwise and stacked from right to left, with one frame
+/// per group of columns that fits into the region's width.
+fn finalize_vertical(
+    vt: &mut Vt,
+    p: &Preparation,
+    lines: &[Line],
+    region: Size,
+) -> SourceResult<Fragment> {
+    let leading = ParElem::leading_in(p.styles);
+
+    // Group the committed lines by the frame they end up in.
+    let mut groups: Vec<Vec<Frame>> = vec![];
+    let mut used = Abs::zero();
+    for line in lines {
+        let frame = commit(vt, p, line, region.y, region.x)?;
+        match groups.last_mut() {
+            Some(group) if region.x.fits(used + leading + frame.height()) => {
+                used += leading + frame.height();
+                group.push(frame);
+            }
+            _ => {
+                used = frame.height();
+                groups.push(vec![frame]);
+            }
+        }
     }
 
+    let frames = groups
+        .into_iter()
+        .map(|group| {
+            let total = group.iter().map(Frame::height).sum::<Abs>()
+                + leading * (group.len() - 1) as f64;
+            let width = if region.x.is_finite() { region.x } else { total };
+            let mut output = Frame::new(Size::new(width, region.y));
+            let mut cursor = width;
+            for mut frame in group {
+                let height = frame.height();
+                frame.transform(Transform::rotate(Angle::deg(90.0)));
+                output.push_frame(Point::with_x(cursor), frame);
+                cursor -= height + leading;
+            }
+            output
+        })
+        .collect();
+
     Ok(Fragment::frames(frames))
 }
 
-/// Merge two line frames
-fn merge(first: &mut Frame, second: Frame, leading: Abs) {
-    let offset = first.height() + leading;
-    let total = offset + second.height();
-    first.push_frame(Point::with_y(offset), second);
-    first.size_mut().y = total;
+/// How often the line positions next to exclusions are refined at most.
+const MAX_WRAP_PASSES: usize = 3;
+
+/// The horizontal space available to the lines of a paragraph that flows
+/// around exclusions.
+#[derive(Clone)]
+struct Wrap<'a> {
+    /// The areas to avoid.
+    exclusions: &'a [Exclusion],
+    /// The full width of the paragraph.
+    width: Abs,
+    /// The estimated height of a line.
+    height: Abs,
+    /// The spacing between lines.
+    leading: Abs,
+    /// The measured top and height of the lines from a previous attempt.
+    lines: Vec<(Abs, Abs)>,
+}
+
+impl<'a> Wrap<'a> {
+    /// Estimate the line positions of a paragraph.
+    ///
+    /// The real line heights are only known after line breaking, so this
+    /// assumes that all lines are as high as the paragraph's tallest text.
+    fn new(vt: &Vt, p: &Preparation, width: Abs, exclusions: &'a [Exclusion]) -> Self {
+        let height = p
+            .items
+            .iter()
+            .filter_map(Item::text)
+            .map(|shaped| {
+                let (top, bottom) = shaped.measure(vt);
+                top + bottom
+            })
+            .max()
+            .unwrap_or_else(|| TextElem::size_in(p.styles));
+        let leading = ParElem::leading_in(p.styles);
+        Self { exclusions, width, height, leading, lines: vec![] }
+    }
+
+    /// Determine the line positions from the real heights of broken lines.
+    fn measure(&self, vt: &Vt, lines: &[Line]) -> Self {
+        let mut top = Abs::zero();
+        let mut measured = Vec::with_capacity(lines.len());
+        for line in lines {
+            let height = line.height(vt);
+            measured.push((top, height));
+            top += height + self.leading;
+        }
+        Self { lines: measured, ..self.clone() }
+    }
+
+    /// The offset and width of the widest span that the line with the given
+    /// index can occupy without overlapping an exclusion.
+    fn span(&self, k: usize) -> (Abs, Abs) {
+        // Lines that weren't measured yet follow the last measured one.
+        let (top, height) = self.lines.get(k).copied().unwrap_or_else(|| {
+            let (top, skip) = match self.lines.last() {
+                Some(&(top, height)) => {
+                    (top + height + self.leading, k - self.lines.len())
+                }
+                None => (Abs::zero(), k),
+            };
+            (top + (self.height + self.leading) * skip as f64, self.height)
+        });
+        let bottom = top + height;
+
+        // Cut the overlapping exclusions out of the full width.
+        let mut spans = vec![(Abs::zero(), self.width)];
+        for exclusion in self.exclusions {
+            let Exclusion { pos, size } = *exclusion;
+            if pos.y >= bottom || pos.y + size.y <= top {
+                continue;
+            }
+
+            spans = spans
+                .into_iter()
+                .flat_map(|(start, end)| {
+                    [(start, end.min(pos.x)), (start.max(pos.x + size.x), end)]
+                })
+                .filter(|(start, end)| start < end)
+                .collect();
+        }
+
+        spans
+            .into_iter()
+            .map(|(start, end)| (start, end - start))
+            .max_by(|a, b| a.1.cmp(&b.1))
+            .unwrap_or((Abs::zero(), Abs::zero()))
+    }
 }
 
 /// Commit to a line and build its frame.
@@ -1410,11 +2356,11 @@ fn commit(
     // Handle hanging punctuation to the left.
     if let Some(Item::Text(text)) = reordered.first() {
         if let Some(glyph) = text.glyphs.first() {
-            if !text.dir.is_positive()
-                && TextElem::overhang_in(text.styles)
+            if TextElem::overhang_in(text.styles)
                 && (reordered.len() > 1 || text.glyphs.len() > 1)
             {
-                let amount = overhang(glyph.c) * glyph.x_advance.at(text.size);
+                let amount = protrusion(text, glyph, !text.dir.is_positive())
+                    * glyph.x_advance.at(text.size);
                 offset -= amount;
                 remaining += amount;
             }
@@ -1424,11 +2370,11 @@ fn commit(
     // Handle hanging punctuation to the right.
     if let Some(Item::Text(text)) = reordered.last() {
         if let Some(glyph) = text.glyphs.last() {
-            if text.dir.is_positive()
-                && TextElem::overhang_in(text.styles)
+            if TextElem::overhang_in(text.styles)
                 && (reordered.len() > 1 || text.glyphs.len() > 1)
             {
-                let amount = overhang(glyph.c) * glyph.x_advance.at(text.size);
+                let amount = protrusion(text, glyph, text.dir.is_positive())
+                    * glyph.x_advance.at(text.size);
                 remaining += amount;
             }
         }
@@ -1443,17 +2389,36 @@ fn commit(
     let mut justification_ratio = 0.0;
     let mut extra_justification = Abs::zero();
 
+    // Use font expansion before adjusting the spaces.
+    let mut expansion_ratio = 0.0;
+    let expand = line.expandability();
+    if line.justify && fr.is_zero() && expand > Abs::zero() {
+        expansion_ratio = (remaining / expand).clamp(-1.0, 1.0);
+        remaining -= expand * expansion_ratio;
+    }
+
+    // Elongate Arabic words with kashidas before stretching the spaces. Each
+    // kashida is at most one em long, the rest is left to the spaces.
+    let mut kashida = Abs::zero();
+    let kashidas = line.kashidas();
+    if line.justify && fr.is_zero() && kashidas > 0 && remaining > Abs::zero() {
+        kashida = (remaining / kashidas as f64).min(Em::one().resolve(p.styles));
+        remaining -= kashida * kashidas as f64;
+    }
+
     let shrink = line.shrinkability();
     let stretch = line.stretchability();
+    let max_shrink = ParElem::max_shrink_in(p.styles).get();
+    let max_stretch = ParElem::max_stretch_in(p.styles).get();
     if remaining < Abs::zero() && shrink > Abs::zero() {
         // Attempt to reduce the length of the line, using shrinkability.
-        justification_ratio = (remaining / shrink).max(-1.0);
-        remaining = (remaining + shrink).min(Abs::zero());
+        justification_ratio = (remaining / shrink).max(-max_shrink);
+        remaining = (remaining + shrink * max_shrink).min(Abs::zero());
     } else if line.justify && fr.is_zero() {
         // Attempt to increase the length of the line, using stretchability.
         if stretch > Abs::zero() {
-            justification_ratio = (remaining / stretch).min(1.0);
-            remaining = (remaining - stretch).max(Abs::zero());
+            justification_ratio = (remaining / stretch).min(max_stretch);
+            remaining = (remaining - stretch * max_stretch).max(Abs::zero());
         }
 
         let justifiables = line.justifiables();
@@ -1469,7 +2434,7 @@ fn commit(
 
     // Build the frames and determine the height and baseline.
     let mut frames = vec![];
-    for item in reordered {
+    for (i, item) in reordered.iter().enumerate() {
         let mut push = |offset: &mut Abs, frame: Frame| {
             let width = frame.width();
             top.set_max(frame.baseline());
@@ -1495,12 +2460,24 @@ fn commit(
                 }
             }
             Item::Text(shaped) => {
-                let frame = shaped.build(vt, justification_ratio, extra_justification);
+                let frame = shaped.build(
+                    vt,
+                    justification_ratio,
+                    extra_justification,
+                    expansion_ratio,
+                    kashida,
+                );
                 push(&mut offset, frame);
             }
             Item::Frame(frame) | Item::Meta(frame) => {
                 push(&mut offset, frame.clone());
             }
+            Item::Ruby(frame, _) => {
+                let (left, right) = ruby_overhang(&reordered, i);
+                offset -= left;
+                push(&mut offset, frame.clone());
+                offset -= right;
+            }
         }
     }
 
@@ -1563,7 +2540,18 @@ fn reorder<'a>(line: &'a Line<'a>) -> (Vec<&Item<'a>>, bool) {
     (reordered, starts_rtl)
 }
 
-/// How much a character should hang into the end margin.
+/// How much a glyph should hang into the margin, taking the configured
+/// protrusion of its text into account. The built-in defaults only apply if
+/// `default` is true, that is at the end of a line.
+fn protrusion(text: &ShapedText, glyph: &ShapedGlyph, default: bool) -> f64 {
+    match TextElem::protrusion_in(text.styles).get(&glyph.font, glyph.c) {
+        Some(amount) => amount.get(),
+        None if default => overhang(glyph.c),
+        None => 0.0,
+    }
+}
+
+/// How much a character should hang into the margin by default.
 ///
 /// For more discussion, see:
 /// https://recoveringphysicist.com/21/
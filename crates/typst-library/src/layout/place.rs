@@ -60,7 +60,28 @@ pub struct PlaceElem {
     /// ```
     pub float: bool,
 
-    /// The amount of clearance the placed element has in a floating layout.
+    /// Whether the text of subsequent paragraphs flows around the placed
+    /// content.
+    ///
+    /// The placed content reserves its area in the current region and the
+    /// lines next to it are shortened to the wider side. Cannot be combined
+    /// with floating placement.
+    ///
+    /// ```example
+    /// #set page(height: 150pt)
+    /// #set par(justify: true)
+    /// #place(
+    ///   top + right,
+    ///   wrap: true,
+    ///   clearance: 6pt,
+    ///   rect(width: 40pt, height: 50pt),
+    /// )
+    /// #lorem(40)
+    /// ```
+    pub wrap: bool,
+
+    /// The amount of clearance the placed element has in a floating layout or
+    /// to the text flowing around it.
     #[default(Em::new(1.5).into())]
     #[resolve]
     pub clearance: Length,
@@ -109,6 +130,8 @@ impl Layout for PlaceElem {
             )
         {
             bail!(self.span(), "floating placement must be `auto`, `top`, or `bottom`");
+        } else if float && self.wrap(styles) {
+            bail!(self.span(), "floating placement cannot wrap text");
         } else if !float && alignment.is_auto() {
             return Err("automatic positioning is only available for floating placement")
                 .hint("you can enable floating placement with `place(float: true, ..)`")
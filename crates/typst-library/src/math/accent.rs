@@ -61,7 +61,7 @@ impl LayoutMath for AccentElem {
         // Preserve class to preserve automatic spacing.
         let base_class = base.class().unwrap_or(MathClass::Normal);
         let base_attach = match &base {
-            MathFragment::Glyph(base) => {
+            MathFragment::Glyph(base) if base.is_math_font(ctx) => {
                 attachment(ctx, base.id, base.italics_correction)
             }
             _ => (base.width() + base.italics_correction()) / 2.0,
@@ -71,12 +71,13 @@ impl LayoutMath for AccentElem {
         // wide in many case.
         let Accent(c) = self.accent();
         let glyph = GlyphFragment::new(ctx, c, self.span());
+        let from_math_font = glyph.is_math_font(ctx);
         let short_fall = ACCENT_SHORT_FALL.scaled(ctx);
         let variant = glyph.stretch_horizontal(ctx, base.width(), short_fall);
         let accent = variant.frame;
         let accent_attach = match variant.id {
-            Some(id) => attachment(ctx, id, variant.italics_correction),
-            None => accent.width() / 2.0,
+            Some(id) if from_math_font => attachment(ctx, id, variant.italics_correction),
+            _ => accent.width() / 2.0,
         };
 
         // Descent is negative because the accent's ink bottom is above the
@@ -1,3 +1,4 @@
+use rustybuzz::Feature;
 use ttf_parser::gsub::SubstitutionSubtable;
 use ttf_parser::math::MathValue;
 use typst::font::{FontStyle, FontWeight};
@@ -34,7 +35,8 @@ pub struct MathContext<'a, 'b, 'v> {
     pub table: ttf_parser::math::Table<'a>,
     pub constants: ttf_parser::math::Constants<'a>,
     pub ssty_table: Option<ttf_parser::gsub::AlternateSubstitution<'a>>,
-    pub glyphwise_tables: Option<Vec<GlyphwiseSubsts<'a>>>,
+    pub features: Vec<Feature>,
+    pub feature_substs: Vec<GlyphwiseSubsts<'a>>,
     pub space_width: Em,
     pub fragments: Vec<MathFragment>,
     pub local: Styles,
@@ -69,13 +71,21 @@ impl<'a, 'b, 'v> MathContext<'a, 'b, 'v> {
                 _ => None,
             });
 
-        let features = tags(styles);
-        let glyphwise_tables = gsub_table.map(|gsub| {
-            features
-                .into_iter()
-                .filter_map(|feature| GlyphwiseSubsts::new(gsub, feature))
-                .collect()
-        });
+        // The features are the same for all glyphs of the equation, so the
+        // math font's substitutions for them are collected only once. Disabled
+        // features don't substitute anything.
+        let features: Vec<Feature> = tags(styles)
+            .into_iter()
+            .filter(|feature| feature.value != 0)
+            .collect();
+        let feature_substs = gsub_table
+            .map(|gsub| {
+                features
+                    .iter()
+                    .flat_map(|&feature| GlyphwiseSubsts::new(gsub, feature))
+                    .collect()
+            })
+            .unwrap_or_default();
 
         let size = TextElem::size_in(styles);
         let ttf = font.ttf();
@@ -94,7 +104,8 @@ impl<'a, 'b, 'v> MathContext<'a, 'b, 'v> {
             table: math_table,
             constants,
             ssty_table,
-            glyphwise_tables,
+            features,
+            feature_substs,
             space_width,
             fragments: vec![],
             local: Styles::new(),
@@ -226,6 +237,7 @@ impl<'a, 'b, 'v> MathContext<'a, 'b, 'v> {
                     false,
                     Size::splat(Abs::inf()),
                     false,
+                    &[],
                 )?
                 .into_frame();
             FrameFragment::new(self, frame)
@@ -240,6 +252,30 @@ impl<'a, 'b, 'v> MathContext<'a, 'b, 'v> {
         self.outer.chain(&self.local)
     }
 
+    /// Find a font for a character that the math font lacks.
+    ///
+    /// Tries the families of the font list first and then, if allowed, the
+    /// last resort fallback fonts.
+    pub fn fallback_font(&self, c: char) -> Option<Font> {
+        let world = self.vt.world;
+        let book = world.book();
+        let styles = self.styles();
+        let variant = variant(styles);
+        let covers = |font: &Font| font.ttf().glyph_index(c).is_some();
+        families(styles)
+            .filter_map(|family| book.select(family.as_str(), variant))
+            .filter_map(|id| world.font(id))
+            .find(covers)
+            .or_else(|| {
+                if !TextElem::fallback_in(styles) {
+                    return None;
+                }
+                let text = c.to_string();
+                let id = book.select_fallback(Some(self.font.info()), variant, &text)?;
+                world.font(id).filter(covers)
+            })
+    }
+
     pub fn realize(&mut self, content: &Content) -> SourceResult<Option<Content>> {
         realize(self.vt, content, self.outer.chain(&self.local))
     }
@@ -201,19 +201,41 @@ pub struct GlyphFragment {
 
 impl GlyphFragment {
     pub fn new(ctx: &MathContext, c: char, span: Span) -> Self {
-        let id = ctx.ttf.glyph_index(c).unwrap_or_default();
-        let id = Self::adjust_glyph_index(ctx, id);
-        Self::with_id(ctx, c, id, span)
+        if let Some(id) = ctx.ttf.glyph_index(c) {
+            let id = Self::adjust_glyph_index(ctx, ctx.font, id);
+            return Self::with_id(ctx, c, id, span);
+        }
+
+        // The math font doesn't have the glyph, so we borrow it from another
+        // font. Such glyphs can't be stretched or scaled for scripts.
+        match ctx.fallback_font(c) {
+            Some(font) => {
+                let id = font.ttf().glyph_index(c).unwrap_or_default();
+                let id = Self::adjust_glyph_index(ctx, &font, id);
+                Self::with_font(ctx, font, c, id, span)
+            }
+            None => Self::with_id(ctx, c, GlyphId(0), span),
+        }
     }
 
     pub fn try_new(ctx: &MathContext, c: char, span: Span) -> Option<Self> {
         let c = ctx.style.styled_char(c);
         let id = ctx.ttf.glyph_index(c)?;
-        let id = Self::adjust_glyph_index(ctx, id);
+        let id = Self::adjust_glyph_index(ctx, ctx.font, id);
         Some(Self::with_id(ctx, c, id, span))
     }
 
     pub fn with_id(ctx: &MathContext, c: char, id: GlyphId, span: Span) -> Self {
+        Self::with_font(ctx, ctx.font.clone(), c, id, span)
+    }
+
+    fn with_font(
+        ctx: &MathContext,
+        font: Font,
+        c: char,
+        id: GlyphId,
+        span: Span,
+    ) -> Self {
         let class = match c {
             ':' => Some(MathClass::Relation),
             '.' | '/' | '⋯' | '⋱' | '⋰' | '⋮' => Some(MathClass::Normal),
@@ -222,7 +244,7 @@ impl GlyphFragment {
         let mut fragment = Self {
             id,
             c,
-            font: ctx.font.clone(),
+            font,
             lang: TextElem::lang_in(ctx.styles()),
             fill: TextElem::fill_in(ctx.styles()),
             style: ctx.style,
@@ -240,36 +262,55 @@ impl GlyphFragment {
         fragment
     }
 
-    /// Apply GSUB substitutions.
-    fn adjust_glyph_index(ctx: &MathContext, id: GlyphId) -> GlyphId {
-        if let Some(glyphwise_tables) = &ctx.glyphwise_tables {
-            glyphwise_tables.iter().fold(id, |id, table| table.apply(id))
-        } else {
-            id
+    /// Apply the GSUB substitutions of the active font features, like
+    /// stylistic sets and character variants.
+    fn adjust_glyph_index(ctx: &MathContext, font: &Font, id: GlyphId) -> GlyphId {
+        if font == ctx.font {
+            return ctx.feature_substs.iter().fold(id, |id, table| table.apply(id));
         }
+
+        // Fallback glyphs are rare, so their font's substitutions are looked
+        // up when needed.
+        let Some(gsub) = font.ttf().tables().gsub else { return id };
+        ctx.features
+            .iter()
+            .flat_map(|&feature| GlyphwiseSubsts::new(gsub, feature))
+            .fold(id, |id, table| table.apply(id))
+    }
+
+    /// Whether the glyph comes from the math font rather than a fallback font.
+    ///
+    /// Only glyphs of the math font have math-specific metrics, variants and
+    /// assemblies.
+    pub fn is_math_font(&self, ctx: &MathContext) -> bool {
+        self.font == *ctx.font
     }
 
     /// Sets element id and boxes in appropriate way without changing other
     /// styles. This is used to replace the glyph with a stretch variant.
     pub fn set_id(&mut self, ctx: &MathContext, id: GlyphId) {
-        let advance = ctx.ttf.glyph_hor_advance(id).unwrap_or_default();
-        let italics = italics_correction(ctx, id).unwrap_or_default();
-        let bbox = ctx.ttf.glyph_bounding_box(id).unwrap_or(Rect {
+        let ttf = self.font.ttf();
+        let advance = ttf.glyph_hor_advance(id).unwrap_or_default();
+        let bbox = ttf.glyph_bounding_box(id).unwrap_or(Rect {
             x_min: 0,
             y_min: 0,
             x_max: 0,
             y_max: 0,
         });
 
-        let mut width = advance.scaled(ctx);
-        if !is_extended_shape(ctx, id) {
-            width += italics;
+        let mut width = self.font.to_em(advance).scaled(ctx);
+        let mut italics = Abs::zero();
+        if self.is_math_font(ctx) {
+            italics = italics_correction(ctx, id).unwrap_or_default();
+            if !is_extended_shape(ctx, id) {
+                width += italics;
+            }
         }
 
         self.id = id;
         self.width = width;
-        self.ascent = bbox.y_max.scaled(ctx);
-        self.descent = -bbox.y_min.scaled(ctx);
+        self.ascent = self.font.to_em(bbox.y_max).scaled(ctx);
+        self.descent = -self.font.to_em(bbox.y_min).scaled(ctx);
         self.italics_correction = italics;
     }
 
@@ -305,6 +346,9 @@ impl GlyphFragment {
                 range: 0..self.c.len_utf8() as u16,
                 span: (self.span, 0),
             }],
+            scale: Ratio::one(),
+            bold: Em::zero(),
+            skew: Ratio::zero(),
         };
         let size = Size::new(self.width, self.ascent + self.descent);
         let mut frame = Frame::new(size);
@@ -315,6 +359,10 @@ impl GlyphFragment {
     }
 
     pub fn make_scriptsize(&mut self, ctx: &MathContext) {
+        if !self.is_math_font(ctx) {
+            return;
+        }
+
         let alt_id =
             script_alternatives(ctx, self.id).and_then(|alts| alts.alternates.get(0));
 
@@ -324,6 +372,10 @@ impl GlyphFragment {
     }
 
     pub fn make_scriptscriptsize(&mut self, ctx: &MathContext) {
+        if !self.is_math_font(ctx) {
+            return;
+        }
+
         let alts = script_alternatives(ctx, self.id);
         let alt_id = alts
             .and_then(|alts| alts.alternates.get(1).or_else(|| alts.alternates.get(0)));
@@ -462,34 +514,52 @@ fn kern_at_height(
     Some(kern.kern(i)?.scaled(ctx))
 }
 
-/// An OpenType substitution table that is applicable to glyph-wise substitutions.
-pub enum GlyphwiseSubsts<'a> {
+/// A lookup of an OpenType feature that is applicable to glyph-wise
+/// substitutions.
+pub struct GlyphwiseSubsts<'a> {
+    subtables: Vec<GlyphwiseSubst<'a>>,
+    value: u32,
+}
+
+/// A single or alternate substitution subtable.
+enum GlyphwiseSubst<'a> {
     Single(SingleSubstitution<'a>),
-    Alternate(AlternateSubstitution<'a>, u32),
+    Alternate(AlternateSubstitution<'a>),
 }
 
 impl<'a> GlyphwiseSubsts<'a> {
-    pub fn new(gsub: LayoutTable<'a>, feature: Feature) -> Option<Self> {
-        let table = gsub
-            .features
-            .find(feature.tag)
-            .and_then(|feature| feature.lookup_indices.get(0))
-            .and_then(|index| gsub.lookups.get(index))?;
-        let table = table.subtables.get::<SubstitutionSubtable>(0)?;
-        match table {
-            SubstitutionSubtable::Single(single_glyphs) => {
-                Some(Self::Single(single_glyphs))
-            }
-            SubstitutionSubtable::Alternate(alt_glyphs) => {
-                Some(Self::Alternate(alt_glyphs, feature.value))
-            }
-            _ => None,
-        }
-    }
-
+    /// Collect all lookups of a feature that consist of single or alternate
+    /// substitutions.
+    pub fn new(gsub: LayoutTable<'a>, feature: Feature) -> Vec<Self> {
+        let Some(found) = gsub.features.find(feature.tag) else { return vec![] };
+        found
+            .lookup_indices
+            .into_iter()
+            .filter_map(|index| gsub.lookups.get(index))
+            .map(|lookup| Self {
+                subtables: lookup
+                    .subtables
+                    .into_iter::<SubstitutionSubtable>()
+                    .filter_map(|subtable| match subtable {
+                        SubstitutionSubtable::Single(single) => {
+                            Some(GlyphwiseSubst::Single(single))
+                        }
+                        SubstitutionSubtable::Alternate(alternate) => {
+                            Some(GlyphwiseSubst::Alternate(alternate))
+                        }
+                        _ => None,
+                    })
+                    .collect(),
+                value: feature.value,
+            })
+            .filter(|lookup| !lookup.subtables.is_empty())
+            .collect()
+    }
+
+    /// Apply the first subtable of the lookup that covers the glyph.
     pub fn try_apply(&self, glyph_id: GlyphId) -> Option<GlyphId> {
-        match self {
-            Self::Single(single) => match single {
+        self.subtables.iter().find_map(|subtable| match subtable {
+            GlyphwiseSubst::Single(single) => match single {
                 SingleSubstitution::Format1 { coverage, delta } => coverage
                     .get(glyph_id)
                     .map(|_| GlyphId(glyph_id.0.wrapping_add(*delta as u16))),
@@ -497,12 +567,12 @@ impl<'a> GlyphwiseSubsts<'a> {
                     coverage.get(glyph_id).and_then(|idx| substitutes.get(idx))
                 }
             },
-            Self::Alternate(alternate, value) => alternate
+            GlyphwiseSubst::Alternate(alternate) => alternate
                 .coverage
                 .get(glyph_id)
                 .and_then(|idx| alternate.alternate_sets.get(idx))
-                .and_then(|set| set.alternates.get(*value as u16)),
-        }
+                .and_then(|set| set.alternates.get(self.value as u16)),
+        })
     }
 
     pub fn apply(&self, glyph_id: GlyphId) -> GlyphId {
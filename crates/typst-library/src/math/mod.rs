@@ -191,6 +191,33 @@ pub struct EquationElem {
     /// ```
     pub supplement: Smart<Option<Supplement>>,
 
+    /// Whether to break equations that are too wide for the line or page
+    /// into multiple lines.
+    ///
+    /// Lines break after binary operators and relations. Inline equations
+    /// are broken by the paragraph like text. For block-level equations, all
+    /// lines but the first are indented by the
+    /// [`wrap-indent`]($func/math.equation.wrap-indent).
+    ///
+    /// ```example
+    /// #set page(width: 160pt)
+    /// #set math.equation(wrap: true)
+    ///
+    /// Expanding $(a + b)^4$ yields
+    /// $a^4 + 4 a^3 b + 6 a^2 b^2 + 4 a b^3 + b^4$.
+    ///
+    /// $ (a + b)^4 = a^4 + 4 a^3 b
+    ///     + 6 a^2 b^2 + 4 a b^3 + b^4 $
+    /// ```
+    #[default(false)]
+    pub wrap: bool,
+
+    /// How far to indent the continuation lines of a
+    /// [wrapped]($func/math.equation.wrap) equation.
+    #[resolve]
+    #[default(Em::new(2.0).into())]
+    pub wrap_indent: Length,
+
     /// The contents of the equation.
     #[required]
     pub body: Content,
@@ -234,6 +261,41 @@ impl Finalize for EquationElem {
     }
 }
 
+impl EquationElem {
+    /// Layout an inline equation into pieces between which the paragraph may
+    /// break lines if wrapping is enabled.
+    pub fn layout_inline(
+        &self,
+        vt: &mut Vt,
+        styles: StyleChain,
+        regions: Regions,
+    ) -> SourceResult<Vec<MathParItem>> {
+        if self.block(styles) || !self.wrap(styles) {
+            let frame = self.layout(vt, styles, regions)?.into_frame();
+            return Ok(vec![MathParItem::Frame(frame)]);
+        }
+
+        let font = find_math_font(vt, styles, self.span())?;
+        let mut ctx = MathContext::new(vt, styles, regions, &font, false);
+        let row = MathRow::new(ctx.layout_fragments(self)?);
+        let mut items = if row.iter().any(|frag| matches!(frag, MathFragment::Linebreak))
+        {
+            vec![MathParItem::Frame(row.into_frame(&ctx))]
+        } else {
+            row.into_par_items()
+        };
+
+        for item in &mut items {
+            if let MathParItem::Frame(frame) = item {
+                fit_to_line(frame, styles, &font);
+                frame.meta(styles, false);
+            }
+        }
+
+        Ok(items)
+    }
+}
+
 impl Layout for EquationElem {
     #[tracing::instrument(name = "EquationElem::layout", skip_all)]
     fn layout(
@@ -245,61 +307,61 @@ impl Layout for EquationElem {
         const NUMBER_GUTTER: Em = Em::new(0.5);
 
         let block = self.block(styles);
+        let font = find_math_font(vt, styles, self.span())?;
 
-        // Find a math font.
-        let variant = variant(styles);
-        let world = vt.world;
-        let Some(font) = families(styles)
-            .find_map(|family| {
-                let id = world.book().select(family.as_str(), variant)?;
-                let font = world.font(id)?;
-                let _ = font.ttf().tables().math?.constants?;
-                Some(font)
-            })
-        else {
-            bail!(self.span(), "current font does not support math");
-        };
-
-        let mut ctx = MathContext::new(vt, styles, regions, &font, block);
-        let mut frame = ctx.layout_frame(self)?;
-
+        let mut counter = None;
         if block {
             if let Some(numbering) = self.numbering(styles) {
                 let pod = Regions::one(regions.base(), Axes::splat(false));
-                let counter = Counter::of(Self::func())
-                    .display(Some(numbering), false)
-                    .layout(vt, styles, pod)?
-                    .into_frame();
-
-                let width = if regions.size.x.is_finite() {
-                    regions.size.x
-                } else {
-                    frame.width()
-                        + 2.0 * (counter.width() + NUMBER_GUTTER.resolve(styles))
-                };
-
-                let height = frame.height().max(counter.height());
-                frame.resize(Size::new(width, height), Align::CENTER_HORIZON);
-
-                let x = if TextElem::dir_in(styles).is_positive() {
-                    frame.width() - counter.width()
-                } else {
-                    Abs::zero()
-                };
-                let y = (frame.height() - counter.height()) / 2.0;
-
-                frame.push_frame(Point::new(x, y), counter)
+                counter = Some(
+                    Counter::of(Self::func())
+                        .display(Some(numbering), false)
+                        .layout(vt, styles, pod)?
+                        .into_frame(),
+                );
+            }
+        }
+
+        let mut ctx = MathContext::new(vt, styles, regions, &font, block);
+        let mut frame = if block && self.wrap(styles) && regions.size.x.is_finite() {
+            // Keep the equation clear of the number on both sides so that it
+            // stays centered.
+            let mut width = regions.size.x;
+            if let Some(counter) = &counter {
+                width -= 2.0 * (counter.width() + NUMBER_GUTTER.resolve(styles));
+            }
+
+            let indent = self.wrap_indent(styles);
+            let row = MathRow::new(ctx.layout_fragments(self)?);
+            if row.iter().any(|frag| matches!(frag, MathFragment::Linebreak)) {
+                row.into_frame(&ctx)
+            } else {
+                row.into_wrapped_frame(&ctx, width, indent)
             }
         } else {
-            let slack = ParElem::leading_in(styles) * 0.7;
-            let top_edge = TextElem::top_edge_in(styles).resolve(styles, &font, None);
-            let bottom_edge =
-                -TextElem::bottom_edge_in(styles).resolve(styles, &font, None);
-
-            let ascent = top_edge.max(frame.ascent() - slack);
-            let descent = bottom_edge.max(frame.descent() - slack);
-            frame.translate(Point::with_y(ascent - frame.baseline()));
-            frame.size_mut().y = ascent + descent;
+            ctx.layout_frame(self)?
+        };
+
+        if let Some(counter) = counter {
+            let width = if regions.size.x.is_finite() {
+                regions.size.x
+            } else {
+                frame.width() + 2.0 * (counter.width() + NUMBER_GUTTER.resolve(styles))
+            };
+
+            let height = frame.height().max(counter.height());
+            frame.resize(Size::new(width, height), Align::CENTER_HORIZON);
+
+            let x = if TextElem::dir_in(styles).is_positive() {
+                frame.width() - counter.width()
+            } else {
+                Abs::zero()
+            };
+            let y = (frame.height() - counter.height()) / 2.0;
+
+            frame.push_frame(Point::new(x, y), counter)
+        } else if !block {
+            fit_to_line(&mut frame, styles, &font);
         }
 
         // Apply metadata.
@@ -309,6 +371,43 @@ impl Layout for EquationElem {
     }
 }
 
+/// Find the first font in the font list that supports math.
+fn find_math_font(vt: &Vt, styles: StyleChain, span: Span) -> SourceResult<Font> {
+    let variant = variant(styles);
+    let world = vt.world;
+    let Some(font) = families(styles).find_map(|family| {
+        let id = world.book().select(family.as_str(), variant)?;
+        let font = world.font(id)?;
+        let _ = font.ttf().tables().math?.constants?;
+        Some(font)
+    }) else {
+        bail!(span, "current font does not support math");
+    };
+    Ok(font)
+}
+
+/// Shrink the vertical extent of an inline equation frame so that it only
+/// increases the line height if it's considerably taller than the text.
+fn fit_to_line(frame: &mut Frame, styles: StyleChain, font: &Font) {
+    let slack = ParElem::leading_in(styles) * 0.7;
+    let top_edge = TextElem::top_edge_in(styles).resolve(styles, font, None);
+    let bottom_edge = -TextElem::bottom_edge_in(styles).resolve(styles, font, None);
+
+    let ascent = top_edge.max(frame.ascent() - slack);
+    let descent = bottom_edge.max(frame.descent() - slack);
+    frame.translate(Point::with_y(ascent - frame.baseline()));
+    frame.size_mut().y = ascent + descent;
+}
+
+/// A piece of an inline equation in a paragraph.
+#[derive(Debug, Clone)]
+pub enum MathParItem {
+    /// Spacing at which the paragraph may break the line.
+    Space(Abs),
+    /// A laid out part of the equation.
+    Frame(Frame),
+}
+
 impl Count for EquationElem {
     fn update(&self) -> Option<CounterUpdate> {
         (self.block(StyleChain::default())
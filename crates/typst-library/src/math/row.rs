@@ -176,6 +176,124 @@ impl MathRow {
         }
     }
 
+    /// Split a single-line row into pieces between which lines may break.
+    ///
+    /// Breaks are possible after binary operators and after relations that
+    /// aren't directly followed by another relation. The spacing after such an
+    /// operator becomes a separate item at which the line may break.
+    pub fn into_par_items(self) -> Vec<MathParItem> {
+        let mut items = vec![];
+        let mut piece = vec![];
+        let mut after_break = false;
+        let mut iter = self.0.into_iter().peekable();
+
+        while let Some(fragment) = iter.next() {
+            if after_break {
+                if let MathFragment::Space(width) | MathFragment::Spacing(width) =
+                    fragment
+                {
+                    items.push(MathParItem::Space(width));
+                    continue;
+                }
+            }
+
+            let breakable = match fragment.class() {
+                Some(MathClass::Binary) => true,
+                Some(MathClass::Relation) => !matches!(
+                    iter.peek().and_then(MathFragment::class),
+                    Some(MathClass::Relation)
+                ),
+                _ => false,
+            };
+
+            piece.push(fragment);
+            after_break = breakable && iter.peek().is_some();
+            if after_break {
+                let row = Self(std::mem::take(&mut piece));
+                items.push(MathParItem::Frame(row.into_line_frame(&[], Align::Left)));
+                if !matches!(
+                    iter.peek(),
+                    Some(MathFragment::Space(_) | MathFragment::Spacing(_))
+                ) {
+                    items.push(MathParItem::Space(Abs::zero()));
+                }
+            }
+        }
+
+        if !piece.is_empty() || items.is_empty() {
+            items.push(MathParItem::Frame(Self(piece).into_line_frame(&[], Align::Left)));
+        }
+
+        items
+    }
+
+    /// Break a single-line row into lines that fit into the given width.
+    ///
+    /// All lines but the first are indented. A row that fits is laid out as a
+    /// single line.
+    pub fn into_wrapped_frame(self, ctx: &MathContext, width: Abs, indent: Abs) -> Frame {
+        let mut lines: Vec<(Vec<(Abs, Frame)>, Abs)> = vec![];
+        let mut line = vec![];
+        let mut x = Abs::zero();
+        let mut space = Abs::zero();
+
+        for item in self.into_par_items() {
+            let frame = match item {
+                MathParItem::Space(width) => {
+                    space += width;
+                    continue;
+                }
+                MathParItem::Frame(frame) => frame,
+            };
+
+            let available = if lines.is_empty() { width } else { width - indent };
+            if !line.is_empty() && x + space + frame.width() > available {
+                lines.push((std::mem::take(&mut line), x));
+                x = Abs::zero();
+            } else if !line.is_empty() {
+                x += space;
+            }
+
+            space = Abs::zero();
+            let advance = frame.width();
+            line.push((x, frame));
+            x += advance;
+        }
+        lines.push((line, x));
+
+        let leading = ParElem::leading_in(ctx.styles());
+        let mut frame = Frame::new(Size::zero());
+        for (i, (pieces, line_width)) in lines.into_iter().enumerate() {
+            let ascent =
+                pieces.iter().map(|(_, f)| f.baseline()).max().unwrap_or_default();
+            let descent = pieces
+                .iter()
+                .map(|(_, f)| f.height() - f.baseline())
+                .max()
+                .unwrap_or_default();
+
+            let size = frame.size_mut();
+            if i > 0 {
+                size.y += leading;
+            }
+
+            let offset = if i > 0 { indent } else { Abs::zero() };
+            let y = size.y + ascent;
+            size.y += ascent + descent;
+            size.x.set_max(offset + line_width);
+            if i == 0 {
+                frame.set_baseline(y);
+            }
+
+            for (x, piece) in pieces {
+                let pos = Point::new(offset + x, y - piece.baseline());
+                frame.push_frame(pos, piece);
+            }
+        }
+
+        frame
+    }
+
     fn into_line_frame(self, points: &[Abs], align: Align) -> Frame {
         let ascent = self.ascent();
         let mut frame = Frame::new(Size::new(Abs::zero(), ascent + self.descent()));
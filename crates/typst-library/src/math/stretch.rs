@@ -38,6 +38,11 @@ fn stretch_glyph(
     short_fall: Abs,
     horizontal: bool,
 ) -> VariantFragment {
+    // Glyphs from fallback fonts have no variants or assemblies.
+    if !base.is_math_font(ctx) {
+        return base.into_variant();
+    }
+
     let short_target = target - short_fall;
     let mut min_overlap = Abs::zero();
     let construction = ctx
@@ -62,7 +67,7 @@ fn stretch_glyph(
 
     // Search for a pre-made variant with a good advance.
     let mut best_id = base.id;
-    let mut best_advance = base.width;
+    let mut best_advance = advance;
     for variant in construction.variants {
         best_id = variant.variant_glyph;
         best_advance = base.font.to_em(variant.advance_measurement).at(base.font_size);
@@ -119,7 +124,7 @@ fn assemble(
             full += advance;
         }
 
-        if full < target {
+        if full < target && growable > Abs::zero() {
             let delta = target - full;
             ratio = (delta / growable).min(1.0);
             full += ratio * growable;
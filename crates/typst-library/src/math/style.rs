@@ -80,9 +80,17 @@ pub fn sans(
 
 /// Calligraphic font style in math.
 ///
+/// Math fonts that offer both a chancery and a roundhand script style usually
+/// provide one of them through a stylistic set (`ssXX`) or character variant
+/// (`cvXX`). You can switch between them with the text
+/// [`features`]($func/text.features), which apply to math, too.
+///
 /// ## Example { #example }
 /// ```example
 /// Let $cal(P)$ be the set of ...
+///
+/// #set text(features: ("ss01",))
+/// Let $cal(P)$ be the set of ...
 /// ```
 ///
 /// Display: Calligraphic
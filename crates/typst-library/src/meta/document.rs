@@ -48,6 +48,8 @@ impl LayoutRoot for DocumentElem {
         tracing::info!("Document layout");
 
         let mut pages = vec![];
+        let mut lines = 0;
+        let mut marks = vec![];
 
         for mut child in &self.children() {
             let outer = styles;
@@ -59,7 +61,7 @@ impl LayoutRoot for DocumentElem {
 
             if let Some(page) = child.to::<PageElem>() {
                 let number = NonZeroUsize::ONE.saturating_add(pages.len());
-                let fragment = page.layout(vt, styles, number)?;
+                let fragment = page.layout(vt, styles, number, &mut lines, &mut marks)?;
                 pages.extend(fragment);
             } else {
                 bail!(child.span(), "unexpected document child");
@@ -2,7 +2,7 @@ use comemo::Prehashed;
 use std::str::FromStr;
 
 use super::{Counter, Numbering, NumberingPattern};
-use crate::layout::{HElem, ParElem};
+use crate::layout::{BlockElem, HElem, ParElem, VElem};
 use crate::meta::{Count, CounterUpdate};
 use crate::prelude::*;
 use crate::text::{SuperElem, TextElem, TextSize};
@@ -87,6 +87,23 @@ pub struct FootnoteElem {
     #[default(Numbering::Pattern(NumberingPattern::from_str("1").unwrap()))]
     pub numbering: Numbering,
 
+    /// Where to place the footnote's entry.
+    ///
+    /// By default, entries are placed at the bottom of the page that
+    /// references them. In multi-column layouts, they are placed at the bottom
+    /// of the referencing column. With `{"end"}`, the entries are collected and
+    /// listed where the next [`endnotes`]($func/endnotes) element is placed.
+    ///
+    /// ```example
+    /// #set footnote(placement: "end")
+    ///
+    /// Endnotes are listed
+    /// later.#footnote[Like this one.]
+    ///
+    /// #endnotes()
+    /// ```
+    pub placement: FootnotePlacement,
+
     /// The content to put into the footnote. Can also be the label of another
     /// footnote this one should point to.
     #[required]
@@ -135,6 +152,7 @@ impl FootnoteElem {
 impl Synthesize for FootnoteElem {
     fn synthesize(&mut self, _vt: &mut Vt, styles: StyleChain) -> SourceResult<()> {
         self.push_numbering(self.numbering(styles));
+        self.push_placement(self.placement(styles));
         Ok(())
     }
 }
@@ -161,6 +179,76 @@ impl Count for FootnoteElem {
     }
 }
 
+/// Where footnote entries are placed.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash, Cast)]
+pub enum FootnotePlacement {
+    /// At the bottom of the page or column that references the note.
+    #[default]
+    Page,
+    /// Collected and listed by the next `endnotes` element.
+    End,
+}
+
+/// A listing of endnotes.
+///
+/// Lists the entries of all footnotes with [`{"end"}`
+/// placement]($func/footnote.placement) since the previous listing. This
+/// way, endnotes can be collected per chapter or at the end of the document.
+/// The entries are styled like regular footnote entries and can thus be
+/// customized with [`footnote.entry`]($func/footnote.entry).
+///
+/// ## Example { #example }
+/// ```example
+/// #set footnote(placement: "end")
+///
+/// = Chapter
+/// Some text.#footnote[A note.]
+/// More text.#footnote[Another note.]
+///
+/// #endnotes()
+/// ```
+///
+/// Display: Endnotes
+/// Category: meta
+#[element(Locatable, Show)]
+pub struct EndnotesElem {}
+
+impl Show for EndnotesElem {
+    #[tracing::instrument(name = "EndnotesElem::show", skip_all)]
+    fn show(&self, vt: &mut Vt, styles: StyleChain) -> SourceResult<Content> {
+        let loc = self.0.location().unwrap();
+
+        // Only collect the notes since the previous listing.
+        let mut selector = FootnoteElem::func().select().before(loc, false);
+        let previous = vt.introspector.query(&Self::func().select().before(loc, false));
+        if let Some(prev) = previous.last() {
+            selector = selector.after(prev.location().unwrap(), false);
+        }
+
+        let gap = FootnoteEntry::gap_in(styles);
+        let mut seq = vec![];
+        for elem in vt.introspector.query(&selector) {
+            let note = elem.to::<FootnoteElem>().unwrap();
+            if note.is_ref()
+                || note.placement(StyleChain::default()) != FootnotePlacement::End
+            {
+                continue;
+            }
+
+            let entry = FootnoteEntry::new(note.clone()).pack();
+            seq.push(
+                BlockElem::new()
+                    .with_body(Some(entry))
+                    .with_above(VElem::block_around(gap.into()))
+                    .with_below(VElem::block_around(gap.into()))
+                    .pack(),
+            );
+        }
+
+        Ok(Content::sequence(seq))
+    }
+}
+
 /// An entry in a footnote list.
 ///
 /// This function is not intended to be called directly. Instead, it is used
@@ -0,0 +1,172 @@
+use crate::prelude::*;
+
+/// Sets a mark for running headers and footers.
+///
+/// Marks are invisible. Each mark has a key and a value. The page layouter
+/// collects the marks on each page, so that [`pagemark`]($func/pagemark) can
+/// display them in the page's header or footer. This is typically used to show
+/// the current chapter or section on each page.
+///
+/// ## Example { #example }
+/// ```example
+/// #set page(
+///   height: 100pt,
+///   header: pagemark("section"),
+/// )
+/// #show heading: it => {
+///   mark("section", it.body)
+///   it
+/// }
+///
+/// = Introduction
+/// #lorem(12)
+///
+/// = Methods
+/// #lorem(20)
+/// ```
+///
+/// Display: Mark
+/// Category: meta
+#[element(Behave, Show)]
+pub struct MarkElem {
+    /// The key that identifies the kind of mark, for example `{"chapter"}`.
+    #[required]
+    pub key: Str,
+
+    /// The mark's value.
+    #[required]
+    pub body: Content,
+}
+
+impl Show for MarkElem {
+    #[tracing::instrument(name = "MarkElem::show", skip_all)]
+    fn show(&self, _: &mut Vt, _: StyleChain) -> SourceResult<Content> {
+        Ok(MetaElem::new()
+            .pack()
+            .styled(MetaElem::set_data(vec![Meta::Marker(self.clone().pack())])))
+    }
+}
+
+impl Behave for MarkElem {
+    fn behaviour(&self) -> Behaviour {
+        Behaviour::Ignorant
+    }
+}
+
+/// Displays a mark of the current page.
+///
+/// This only has an effect in a page's [header]($func/page.header) or
+/// [footer]($func/page.footer). Elsewhere, it displays nothing. The marks are
+/// resolved by the page layouter, which keeps track of the marks on each page
+/// and those in effect at its top. This way, running headers don't need to
+/// query the whole document on each page.
+///
+/// ```example
+/// #set page(
+///   height: 80pt,
+///   header: [
+///     #pagemark("name", position: "first")
+///     #h(1fr)
+///     #pagemark("name", position: "last")
+///   ],
+/// )
+///
+/// #mark("name")[Aachen] Aachen \
+/// #mark("name")[Aalen] Aalen \
+/// #mark("name")[Abensberg] Abensberg
+/// ```
+///
+/// Display: Page Mark
+/// Category: meta
+#[element(Show)]
+pub struct PagemarkElem {
+    /// The key of the mark to display.
+    #[required]
+    pub key: Str,
+
+    /// Which of the page's marks to display.
+    ///
+    /// When the page doesn't contain a mark with the key, both `{"first"}`
+    /// and `{"last"}` fall back to the `{"top"}` mark.
+    #[default(MarkPosition::First)]
+    pub position: MarkPosition,
+
+    /// The marks of the page that is being laid out.
+    #[internal]
+    pub marks: PageMarks,
+}
+
+impl Show for PagemarkElem {
+    #[tracing::instrument(name = "PagemarkElem::show", skip_all)]
+    fn show(&self, _: &mut Vt, styles: StyleChain) -> SourceResult<Content> {
+        let key = self.key();
+        let marks = self.marks(styles);
+        let top = || marks.top.iter().rev().find(|(k, _)| *k == key);
+        let mut page = marks.page.iter().filter(|(k, _)| *k == key);
+        let mark = match self.position(styles) {
+            MarkPosition::First => page.next().or_else(top),
+            MarkPosition::Last => page.last().or_else(top),
+            MarkPosition::Top => top(),
+        };
+        Ok(mark.map(|(_, body)| body.clone()).unwrap_or_default())
+    }
+}
+
+/// Which of a page's marks to display.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Cast)]
+pub enum MarkPosition {
+    /// The first mark on the page.
+    First,
+    /// The last mark on the page.
+    Last,
+    /// The mark in effect at the top of the page, that is, the last one
+    /// before the page.
+    Top,
+}
+
+/// The marks relevant to a page.
+#[derive(Debug, Default, Clone, PartialEq, Hash)]
+pub struct PageMarks {
+    /// The latest mark for each key before the page.
+    pub top: Vec<(Str, Content)>,
+    /// The marks on the page in layout order.
+    pub page: Vec<(Str, Content)>,
+}
+
+impl PageMarks {
+    /// Collect the marks in a page's frame and update the `current` marks,
+    /// which are the latest marks for each key, accordingly.
+    pub fn collect(frame: &Frame, current: &mut Vec<(Str, Content)>) -> Self {
+        let top = current.clone();
+        let mut page = vec![];
+        find_marks(&mut page, frame);
+
+        for (key, body) in &page {
+            match current.iter_mut().find(|(k, _)| k == key) {
+                Some(slot) => slot.1 = body.clone(),
+                None => current.push((key.clone(), body.clone())),
+            }
+        }
+
+        Self { top, page }
+    }
+}
+
+cast! {
+    type PageMarks: "page marks",
+}
+
+/// Find all marks in a frame in layout order.
+fn find_marks(marks: &mut Vec<(Str, Content)>, frame: &Frame) {
+    for (_, item) in frame.items() {
+        match item {
+            FrameItem::Group(group) => find_marks(marks, &group.frame),
+            FrameItem::Meta(Meta::Marker(content), _) => {
+                if let Some(mark) = content.to::<MarkElem>() {
+                    marks.push((mark.key(), mark.body()));
+                }
+            }
+            _ => {}
+        }
+    }
+}
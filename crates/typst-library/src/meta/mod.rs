@@ -8,11 +8,13 @@ mod figure;
 mod footnote;
 mod heading;
 mod link;
+mod mark;
 mod metadata;
 mod numbering;
 mod outline;
 mod query;
 mod reference;
+mod sidenote;
 mod state;
 
 pub use self::bibliography::*;
@@ -23,11 +25,13 @@ pub use self::figure::*;
 pub use self::footnote::*;
 pub use self::heading::*;
 pub use self::link::*;
+pub use self::mark::*;
 pub use self::metadata::*;
 pub use self::numbering::*;
 pub use self::outline::*;
 pub use self::query::*;
 pub use self::reference::*;
+pub use self::sidenote::*;
 pub use self::state::*;
 
 use crate::prelude::*;
@@ -42,6 +46,10 @@ pub(super) fn define(global: &mut Scope) {
     global.define("heading", HeadingElem::func());
     global.define("figure", FigureElem::func());
     global.define("footnote", FootnoteElem::func());
+    global.define("endnotes", EndnotesElem::func());
+    global.define("sidenote", SidenoteElem::func());
+    global.define("mark", MarkElem::func());
+    global.define("pagemark", PagemarkElem::func());
     global.define("cite", CiteElem::func());
     global.define("bibliography", BibliographyElem::func());
     global.define("locate", locate_func());
@@ -0,0 +1,143 @@
+use std::str::FromStr;
+
+use super::{Count, Counter, CounterUpdate, Numbering, NumberingPattern};
+use crate::layout::{HElem, ParElem};
+use crate::prelude::*;
+use crate::text::{SuperElem, TextElem, TextSize};
+
+/// A side note in the page margin.
+///
+/// Side notes are placed into the outer margin of the page, next to the line
+/// that references them, as popularized by Edward Tufte's books. Like
+/// [footnotes]($func/footnote), they insert a superscript number into the text
+/// and are numbered sequentially throughout the document. Notes that would
+/// overlap are stacked downwards and notes that don't fit on their page any
+/// more continue at the top of the next page's margin.
+///
+/// The outer margin is determined by the page's [binding]($func/page.binding):
+/// For [two-sided]($func/page.margin) pages, notes alternate between the
+/// margins, otherwise they go into the margin opposite to the binding. The
+/// notes take up the width of that margin minus the clearance on both sides,
+/// so make sure to configure a wide enough margin.
+///
+/// ## Example { #example }
+/// ```example
+/// #set page(margin: (right: 100pt))
+/// Side notes are great for
+/// remarks#sidenote[Like this one.]
+/// that shouldn't interrupt the
+/// flow of the text.
+/// ```
+///
+/// _Note:_ Like for footnotes, set and show rules in the scope where
+/// `sidenote` is called may not apply to the note's content.
+///
+/// Display: Sidenote
+/// Category: meta
+#[element(Locatable, Synthesize, Show, Count)]
+pub struct SidenoteElem {
+    /// How to number side notes.
+    ///
+    /// If set to `{none}`, the note is placed into the margin without a
+    /// number and doesn't step the side note counter.
+    ///
+    /// ```example
+    /// #set page(margin: (right: 100pt))
+    /// #set sidenote(numbering: none)
+    /// A remark.#sidenote[Unnumbered.]
+    /// ```
+    #[default(Some(Numbering::Pattern(NumberingPattern::from_str("1").unwrap())))]
+    pub numbering: Option<Numbering>,
+
+    /// The distance between the note and the text area as well as the page
+    /// edge.
+    #[default(Em::new(1.0).into())]
+    #[resolve]
+    pub clearance: Length,
+
+    /// The minimum vertical gap between stacked side notes.
+    #[default(Em::new(0.5).into())]
+    #[resolve]
+    pub gap: Length,
+
+    /// The content to put into the margin.
+    #[required]
+    pub body: Content,
+
+    /// Whether the note steps the side note counter.
+    #[synthesized]
+    pub numbered: bool,
+}
+
+impl Synthesize for SidenoteElem {
+    fn synthesize(&mut self, _vt: &mut Vt, styles: StyleChain) -> SourceResult<()> {
+        let numbering = self.numbering(styles);
+        self.push_numbered(numbering.is_some());
+        self.push_numbering(numbering);
+        Ok(())
+    }
+}
+
+impl Show for SidenoteElem {
+    #[tracing::instrument(name = "SidenoteElem::show", skip_all)]
+    fn show(&self, vt: &mut Vt, styles: StyleChain) -> SourceResult<Content> {
+        Ok(vt.delayed(|vt| {
+            let mut realized = Content::empty();
+            let mut entry = self.body();
+
+            if let Some(numbering) = self.numbering(styles) {
+                let loc = self.0.location().unwrap();
+                let counter = Counter::of(Self::func());
+                let num = counter.at(vt, loc)?.display(vt, &numbering)?;
+                let hole = HElem::new(Abs::zero().into()).with_weak(true).pack();
+                let gap = HElem::new(Em::new(0.05).into()).with_weak(true).pack();
+                realized = hole + SuperElem::new(num.clone()).pack();
+                entry = SuperElem::new(num).pack() + gap + entry;
+            }
+
+            let entry = entry
+                .styled(ParElem::set_leading(Em::new(0.5).into()))
+                .styled(TextElem::set_size(TextSize(Em::new(0.85).into())));
+
+            let marker = SidenoteMarker::new(
+                entry,
+                self.clearance(styles).into(),
+                self.gap(styles).into(),
+            );
+
+            Ok(realized
+                + MetaElem::new()
+                    .pack()
+                    .styled(MetaElem::set_data(vec![Meta::Marker(marker.pack())])))
+        }))
+    }
+}
+
+impl Count for SidenoteElem {
+    fn update(&self) -> Option<CounterUpdate> {
+        self.numbered().then(|| CounterUpdate::Step(NonZeroUsize::ONE))
+    }
+}
+
+/// Marks the position of a side note in its frame.
+///
+/// The page layouter picks these markers up and places the notes into the
+/// margin.
+///
+/// Display: Sidenote Marker
+/// Category: special
+#[element]
+pub struct SidenoteMarker {
+    /// The content to place into the margin.
+    #[required]
+    pub body: Content,
+
+    /// The distance between the note and the text area as well as the page
+    /// edge.
+    #[required]
+    pub clearance: Length,
+
+    /// The minimum vertical gap to the next note.
+    #[required]
+    pub gap: Length,
+}
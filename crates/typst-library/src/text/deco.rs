@@ -1,7 +1,7 @@
 use kurbo::{BezPath, Line, ParamCurve};
 use ttf_parser::{GlyphId, OutlineBuilder};
 
-use super::TextElem;
+use super::{BottomEdge, BottomEdgeMetric, TextElem, TopEdge, TopEdgeMetric};
 use crate::prelude::*;
 
 /// Underlines text.
@@ -73,11 +73,12 @@ impl Show for UnderlineElem {
     #[tracing::instrument(name = "UnderlineElem::show", skip_all)]
     fn show(&self, _: &mut Vt, styles: StyleChain) -> SourceResult<Content> {
         Ok(self.body().styled(TextElem::set_deco(Decoration {
-            line: DecoLine::Underline,
-            stroke: self.stroke(styles).unwrap_or_default(),
-            offset: self.offset(styles),
+            line: DecoLine::Underline {
+                stroke: self.stroke(styles).unwrap_or_default(),
+                offset: self.offset(styles),
+                evade: self.evade(styles),
+            },
             extent: self.extent(styles),
-            evade: self.evade(styles),
         })))
     }
 }
@@ -157,11 +158,12 @@ impl Show for OverlineElem {
     #[tracing::instrument(name = "OverlineElem::show", skip_all)]
     fn show(&self, _: &mut Vt, styles: StyleChain) -> SourceResult<Content> {
         Ok(self.body().styled(TextElem::set_deco(Decoration {
-            line: DecoLine::Overline,
-            stroke: self.stroke(styles).unwrap_or_default(),
-            offset: self.offset(styles),
+            line: DecoLine::Overline {
+                stroke: self.stroke(styles).unwrap_or_default(),
+                offset: self.offset(styles),
+                evade: self.evade(styles),
+            },
             extent: self.extent(styles),
-            evade: self.evade(styles),
         })))
     }
 }
@@ -226,23 +228,97 @@ impl Show for StrikeElem {
     #[tracing::instrument(name = "StrikeElem::show", skip_all)]
     fn show(&self, _: &mut Vt, styles: StyleChain) -> SourceResult<Content> {
         Ok(self.body().styled(TextElem::set_deco(Decoration {
-            line: DecoLine::Strikethrough,
-            stroke: self.stroke(styles).unwrap_or_default(),
-            offset: self.offset(styles),
+            line: DecoLine::Strikethrough {
+                stroke: self.stroke(styles).unwrap_or_default(),
+                offset: self.offset(styles),
+                evade: false,
+            },
             extent: self.extent(styles),
-            evade: false,
         })))
     }
 }
 
-/// Defines a line that is positioned over, under or on top of text.
+/// Highlights text with a background color.
+///
+/// ## Example { #example }
+/// ```example
+/// This is #highlight[important].
+/// ```
+///
+/// Display: Highlight
+/// Category: text
+#[element(Show)]
+pub struct HighlightElem {
+    /// The color to highlight the text with.
+    ///
+    /// ```example
+    /// This is #highlight(fill: blue.lighten(60%))[with blue].
+    /// ```
+    #[default(RgbaColor::new(0xFF, 0xFD, 0x11, 0xA1).into())]
+    pub fill: Paint,
+
+    /// The top end of the background rectangle.
+    ///
+    /// See the [text's documentation]($func/text.top-edge) for more details.
+    ///
+    /// ```example
+    /// #set highlight(top-edge: "ascender")
+    /// #highlight[a] #highlight[aib]
+    ///
+    /// #set highlight(top-edge: "x-height")
+    /// #highlight[a] #highlight[aib]
+    /// ```
+    #[default(TopEdge::Metric(TopEdgeMetric::Ascender))]
+    pub top_edge: TopEdge,
+
+    /// The bottom end of the background rectangle.
+    ///
+    /// See the [text's documentation]($func/text.bottom-edge) for more details.
+    ///
+    /// ```example
+    /// #set highlight(bottom-edge: "descender")
+    /// #highlight[a] #highlight[ap]
+    ///
+    /// #set highlight(bottom-edge: "baseline")
+    /// #highlight[a] #highlight[ap]
+    /// ```
+    #[default(BottomEdge::Metric(BottomEdgeMetric::Descender))]
+    pub bottom_edge: BottomEdge,
+
+    /// The amount by which to extend the background to the sides beyond
+    /// (or within if negative) the content.
+    ///
+    /// ```example
+    /// A long #highlight(extent: 4pt)[background].
+    /// ```
+    #[resolve]
+    pub extent: Length,
+
+    /// The content that should be highlighted.
+    #[required]
+    pub body: Content,
+}
+
+impl Show for HighlightElem {
+    #[tracing::instrument(name = "HighlightElem::show", skip_all)]
+    fn show(&self, _: &mut Vt, styles: StyleChain) -> SourceResult<Content> {
+        Ok(self.body().styled(TextElem::set_deco(Decoration {
+            line: DecoLine::Highlight {
+                fill: self.fill(styles),
+                top_edge: self.top_edge(styles),
+                bottom_edge: self.bottom_edge(styles),
+            },
+            extent: self.extent(styles),
+        })))
+    }
+}
+
+/// Defines a line or background that is positioned over, under or on top of
+/// text.
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct Decoration {
     pub line: DecoLine,
-    pub stroke: PartialStroke<Abs>,
-    pub offset: Smart<Abs>,
     pub extent: Abs,
-    pub evade: bool,
 }
 
 impl Fold for Decoration {
@@ -258,39 +334,60 @@ cast! {
     type Decoration: "decoration",
 }
 
-/// A kind of decorative line.
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+/// A kind of decorative line or background.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum DecoLine {
-    Underline,
-    Strikethrough,
-    Overline,
+    Underline { stroke: PartialStroke<Abs>, offset: Smart<Abs>, evade: bool },
+    Strikethrough { stroke: PartialStroke<Abs>, offset: Smart<Abs>, evade: bool },
+    Overline { stroke: PartialStroke<Abs>, offset: Smart<Abs>, evade: bool },
+    Highlight { fill: Paint, top_edge: TopEdge, bottom_edge: BottomEdge },
 }
 
 /// Add line decorations to a single run of shaped text.
+#[allow(clippy::too_many_arguments)]
 pub(super) fn decorate(
     frame: &mut Frame,
     deco: &Decoration,
     text: &TextItem,
+    styles: StyleChain,
+    size: Abs,
     shift: Abs,
     pos: Point,
     width: Abs,
 ) {
     let font_metrics = text.font.metrics();
-    let metrics = match deco.line {
-        DecoLine::Strikethrough => font_metrics.strikethrough,
-        DecoLine::Overline => font_metrics.overline,
-        DecoLine::Underline => font_metrics.underline,
+    let (stroke, offset, evade, metrics) = match &deco.line {
+        DecoLine::Strikethrough { stroke, offset, evade } => {
+            (stroke, offset, *evade, font_metrics.strikethrough)
+        }
+        DecoLine::Overline { stroke, offset, evade } => {
+            (stroke, offset, *evade, font_metrics.overline)
+        }
+        DecoLine::Underline { stroke, offset, evade } => {
+            (stroke, offset, *evade, font_metrics.underline)
+        }
+        DecoLine::Highlight { fill, top_edge, bottom_edge } => {
+            let (top, bottom) = highlight_edges(text, styles, *top_edge, *bottom_edge);
+            let size = Size::new(width + 2.0 * deco.extent, top + bottom);
+            let rect = Geometry::Rect(size).filled(fill.clone());
+            let origin = Point::new(pos.x - deco.extent, pos.y - top - shift);
+
+            // The background must go behind all glyphs of the text, including
+            // those of other runs that it may overlap because of its extent.
+            frame.prepend(origin, FrameItem::Shape(rect, Span::detached()));
+            return;
+        }
     };
 
-    let offset = deco.offset.unwrap_or(-metrics.position.at(text.size)) - shift;
-    let stroke = deco.stroke.clone().unwrap_or(Stroke {
+    let offset = offset.unwrap_or(-metrics.position.at(size)) - shift;
+    let stroke = stroke.clone().unwrap_or(Stroke {
         paint: text.fill.clone(),
-        thickness: metrics.thickness.at(text.size),
+        thickness: metrics.thickness.at(size),
         ..Stroke::default()
     });
 
-    let gap_padding = 0.08 * text.size;
-    let min_width = 0.162 * text.size;
+    let gap_padding = 0.08 * size;
+    let min_width = 0.162 * size;
 
     let start = pos.x - deco.extent;
     let end = pos.x + (width + 2.0 * deco.extent);
@@ -299,13 +396,13 @@ pub(super) fn decorate(
         let origin = Point::new(from, pos.y + offset);
         let target = Point::new(to - from, Abs::zero());
 
-        if target.x >= min_width || !deco.evade {
+        if target.x >= min_width || !evade {
             let shape = Geometry::Line(target).stroked(stroke.clone());
             frame.push(origin, FrameItem::Shape(shape, Span::detached()));
         }
     };
 
-    if !deco.evade {
+    if !evade {
         push_segment(start, end);
         return;
     }
@@ -366,6 +463,30 @@ pub(super) fn decorate(
     }
 }
 
+/// Determine the distances of a highlight's top and bottom edges from the
+/// baseline.
+fn highlight_edges(
+    text: &TextItem,
+    styles: StyleChain,
+    top_edge: TopEdge,
+    bottom_edge: BottomEdge,
+) -> (Abs, Abs) {
+    let mut top = Abs::zero();
+    let mut bottom = Abs::zero();
+    for glyph in &text.glyphs {
+        let bbox = if top_edge.is_bounds() || bottom_edge.is_bounds() {
+            text.font.ttf().glyph_bounding_box(GlyphId(glyph.id))
+        } else {
+            None
+        };
+
+        top.set_max(top_edge.resolve(styles, &text.font, bbox));
+        bottom.set_max(-bottom_edge.resolve(styles, &text.font, bbox));
+    }
+
+    (top, bottom)
+}
+
 /// Builds a kurbo [`BezPath`] for a glyph.
 struct BezPathBuilder {
     path: BezPath,
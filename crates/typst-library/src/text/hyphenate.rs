@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use typst::diag::FileError;
+use typst::eval::Bytes;
+
+use super::TextElem;
+use crate::prelude::*;
+
+/// The minimum number of characters before a pattern-based hyphen.
+const LEFT_MIN: usize = 2;
+
+/// The minimum number of characters after a pattern-based hyphen.
+const RIGHT_MIN: usize = 3;
+
+/// Hyphenation pattern files for specific languages.
+#[derive(Debug, Default, Clone, PartialEq, Hash)]
+pub struct HyphenationPatterns(pub Vec<(Lang, EcoString)>);
+
+cast! {
+    HyphenationPatterns,
+    self => self.0
+        .into_iter()
+        .map(|(lang, path)| (lang.as_str().into(), path.into_value()))
+        .collect::<Dict>()
+        .into_value(),
+    values: Dict => Self(values
+        .into_iter()
+        .map(|(k, v)| Ok((Lang::from_str(&k)?, v.cast()?)))
+        .collect::<StrResult<_>>()?),
+}
+
+impl Fold for HyphenationPatterns {
+    type Output = Self;
+
+    fn fold(mut self, outer: Self::Output) -> Self::Output {
+        self.0.extend(outer.0);
+        self
+    }
+}
+
+/// Words with explicit hyphenation points for specific languages.
+#[derive(Debug, Default, Clone, PartialEq, Hash)]
+pub struct HyphenationExceptions(pub Vec<(Lang, Vec<EcoString>)>);
+
+cast! {
+    HyphenationExceptions,
+    self => self.0
+        .into_iter()
+        .map(|(lang, words)| (lang.as_str().into(), words.into_value()))
+        .collect::<Dict>()
+        .into_value(),
+    values: Dict => Self(values
+        .into_iter()
+        .map(|(k, v)| Ok((Lang::from_str(&k)?, v.cast()?)))
+        .collect::<StrResult<_>>()?),
+}
+
+impl Fold for HyphenationExceptions {
+    type Output = Self;
+
+    fn fold(mut self, outer: Self::Output) -> Self::Output {
+        self.0.extend(outer.0);
+        self
+    }
+}
+
+/// Split a word into syllables with the exceptions and patterns registered
+/// for the language.
+///
+/// Returns the byte lengths of the syllables or `None` if neither an
+/// exception nor a pattern file applies to the word.
+pub(crate) fn hyphenate(
+    word: &str,
+    lang: Lang,
+    styles: StyleChain,
+) -> Option<Vec<usize>> {
+    let lower: String = word.chars().map(lowercase).collect();
+    let breaks = exception(&lower, lang, styles).or_else(|| {
+        let paths = TextElem::hyphenation_patterns_in(styles);
+        let data = TextElem::hyphenation_patterns_data_in(styles);
+        let (_, bytes) = paths.0.iter().zip(data).find(|((l, _), _)| *l == lang)?;
+        let patterns = load_patterns(bytes).ok()?;
+        Some(patterns.breaks(&lower))
+    })?;
+
+    let offsets: Vec<usize> = word.char_indices().map(|(i, _)| i).collect();
+    let mut syllables = vec![];
+    let mut last = 0;
+    for index in breaks {
+        let offset = offsets[index];
+        syllables.push(offset - last);
+        last = offset;
+    }
+
+    syllables.push(word.len() - last);
+    Some(syllables)
+}
+
+/// Look up a lowercased word in the exception lists for the language.
+fn exception(word: &str, lang: Lang, styles: StyleChain) -> Option<Vec<usize>> {
+    compile_exceptions(TextElem::hyphenation_exceptions_in(styles), lang)
+        .get(word)
+        .cloned()
+}
+
+/// Compile the exception lists for a language into a map from lowercased
+/// words to the character indices of their hyphenation points. The first
+/// entry for a word takes precedence.
+#[comemo::memoize]
+fn compile_exceptions(
+    exceptions: HyphenationExceptions,
+    lang: Lang,
+) -> Arc<HashMap<String, Vec<usize>>> {
+    let mut compiled = HashMap::new();
+    for entry in exceptions
+        .0
+        .iter()
+        .filter(|(l, _)| *l == lang)
+        .flat_map(|(_, words)| words)
+    {
+        let (word, breaks) = split_exception(entry);
+        compiled.entry(word).or_insert(breaks);
+    }
+    Arc::new(compiled)
+}
+
+/// Split an exception like `hy-phen-ation` into the lowercased word and the
+/// character indices of its hyphenation points.
+fn split_exception(entry: &str) -> (String, Vec<usize>) {
+    let mut word = String::new();
+    let mut breaks = vec![];
+    let mut len = 0;
+    for c in entry.chars() {
+        if c == '-' {
+            if len > 0 && breaks.last() != Some(&len) {
+                breaks.push(len);
+            }
+        } else {
+            word.push(lowercase(c));
+            len += 1;
+        }
+    }
+
+    breaks.retain(|&index| index < len);
+    (word, breaks)
+}
+
+/// Lowercase a character while keeping the character count intact.
+fn lowercase(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// Compiled patterns and exceptions from a TeX hyphenation file.
+#[derive(Debug, Default)]
+struct Patterns {
+    /// Maps letter sequences to the levels between and around their letters.
+    patterns: HashMap<String, Vec<u8>>,
+    /// The number of letters in the longest pattern.
+    longest: usize,
+    /// Maps words to the character indices of their hyphenation points.
+    exceptions: HashMap<String, Vec<usize>>,
+}
+
+impl Patterns {
+    /// Parse the contents of a TeX hyphenation file.
+    ///
+    /// Reads the `\patterns` and `\hyphenation` blocks if present. Otherwise,
+    /// the whole file is treated as a whitespace-separated list of patterns.
+    fn parse(text: &str) -> StrResult<Self> {
+        let text = text
+            .lines()
+            .map(|line| line.split('%').next().unwrap_or_default())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let mut patterns = block(&text, "\\patterns")?;
+        let exceptions = block(&text, "\\hyphenation")?;
+        if patterns.is_none() && exceptions.is_none() {
+            patterns = Some(text.as_str());
+        }
+
+        let mut out = Self::default();
+        for pattern in patterns.into_iter().flat_map(str::split_whitespace) {
+            out.add_pattern(pattern)?;
+        }
+
+        for entry in exceptions.into_iter().flat_map(str::split_whitespace) {
+            let (word, breaks) = split_exception(entry);
+            out.exceptions.insert(word, breaks);
+        }
+
+        Ok(out)
+    }
+
+    /// Add a pattern like `1ba` or `.ab3c`.
+    fn add_pattern(&mut self, pattern: &str) -> StrResult<()> {
+        let mut letters = String::new();
+        let mut levels = vec![0];
+        let mut digit = false;
+        for c in pattern.chars() {
+            if let Some(level) = c.to_digit(10) {
+                if digit {
+                    bail!("invalid hyphenation pattern `{pattern}`");
+                }
+                *levels.last_mut().unwrap() = level as u8;
+                digit = true;
+            } else {
+                letters.push(lowercase(c));
+                levels.push(0);
+                digit = false;
+            }
+        }
+
+        if letters.is_empty() {
+            bail!("invalid hyphenation pattern `{pattern}`");
+        }
+
+        self.longest = self.longest.max(levels.len() - 1);
+        self.patterns.insert(letters, levels);
+        Ok(())
+    }
+
+    /// Determine the character indices at which a lowercased word may be
+    /// hyphenated with Liang's algorithm.
+    fn breaks(&self, word: &str) -> Vec<usize> {
+        if let Some(breaks) = self.exceptions.get(word) {
+            return breaks.clone();
+        }
+
+        let dotted = format!(".{word}.");
+        let bounds: Vec<usize> =
+            dotted.char_indices().map(|(i, _)| i).chain([dotted.len()]).collect();
+        let count = bounds.len() - 1;
+
+        // The level at index `i` belongs to the gap before the `i`-th
+        // character of the dotted word.
+        let mut levels = vec![0; count + 1];
+        for start in 0..count {
+            for end in start + 1..=count.min(start + self.longest) {
+                let Some(pattern) =
+                    self.patterns.get(&dotted[bounds[start]..bounds[end]])
+                else {
+                    continue;
+                };
+
+                for (level, &value) in levels[start..].iter_mut().zip(pattern) {
+                    *level = (*level).max(value);
+                }
+            }
+        }
+
+        // Odd levels permit a hyphen. The gap before the `i`-th character of
+        // the word has index `i + 1` because of the leading dot.
+        let len = count - 2;
+        (LEFT_MIN..=len.saturating_sub(RIGHT_MIN))
+            .filter(|&i| levels[i + 1] % 2 == 1)
+            .collect()
+    }
+}
+
+/// Extract the contents of a `\command{...}` block.
+fn block<'a>(text: &'a str, command: &str) -> StrResult<Option<&'a str>> {
+    let Some(start) = text.find(command) else { return Ok(None) };
+    let rest = text[start + command.len()..].trim_start();
+    let Some(rest) = rest.strip_prefix('{') else {
+        bail!("expected opening brace after `{command}`");
+    };
+    let Some(end) = rest.find('}') else {
+        bail!("unclosed `{command}` block");
+    };
+    Ok(Some(&rest[..end]))
+}
+
+/// Load hyphenation patterns from a file buffer.
+#[comemo::memoize]
+fn load_patterns(bytes: Bytes) -> StrResult<Arc<Patterns>> {
+    let text = std::str::from_utf8(&bytes).map_err(FileError::from)?;
+    Patterns::parse(text).map(Arc::new)
+}
+
+/// Function to parse the hyphenation patterns argument.
+pub(super) fn parse_hyphenation_patterns(
+    vm: &mut Vm,
+    args: &mut Args,
+) -> SourceResult<(Option<HyphenationPatterns>, Option<Vec<Bytes>>)> {
+    let Some(Spanned { v: patterns, span }) =
+        args.named::<Spanned<HyphenationPatterns>>("hyphenation-patterns")?
+    else {
+        return Ok((None, None));
+    };
+
+    // Load and check the pattern files.
+    let data = patterns
+        .0
+        .iter()
+        .map(|(_, path)| {
+            let id = vm.location().join(path).at(span)?;
+            let data = vm.world().file(id).at(span)?;
+            load_patterns(data.clone())
+                .map_err(|e| {
+                    eco_format!("failed to parse hyphenation patterns `{path}`: {e}")
+                })
+                .at(span)?;
+            Ok(data)
+        })
+        .collect::<SourceResult<Vec<Bytes>>>()?;
+
+    Ok((Some(patterns), Some(data)))
+}
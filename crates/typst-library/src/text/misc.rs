@@ -272,11 +272,12 @@ impl Case {
 
 /// Displays text in small capitals.
 ///
-/// _Note:_ This enables the OpenType `smcp` feature for the font. Not all fonts
-/// support this feature. Sometimes smallcaps are part of a dedicated font and
-/// sometimes they are not available at all. In the future, this function will
-/// support selecting a dedicated smallcaps font as well as synthesizing
-/// smallcaps from normal letters, but this is not yet implemented.
+/// _Note:_ This enables the OpenType `smcp` feature for the font. For fonts
+/// that don't support this feature, small capitals are synthesized from scaled
+/// down capitals, unless this is disabled with the text function's
+/// [`synthesis`]($func/text.synthesis) parameter. Sometimes smallcaps are
+/// part of a dedicated font. In the future, this function will support
+/// selecting such a dedicated font, but this is not yet implemented.
 ///
 /// ## Example { #example }
 /// ```example
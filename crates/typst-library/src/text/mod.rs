@@ -1,22 +1,31 @@
 //! Text handling.
 
 mod deco;
+mod hyphenate;
 mod misc;
+mod quote;
 mod quotes;
 mod raw;
+mod ruby;
 mod shaping;
 mod shift;
 
 pub use self::deco::*;
+pub use self::hyphenate::*;
 pub use self::misc::*;
+pub use self::quote::*;
 pub use self::quotes::*;
 pub use self::raw::*;
+pub use self::ruby::*;
 pub use self::shaping::*;
 pub use self::shift::*;
 
-use rustybuzz::Tag;
-use ttf_parser::Rect;
+use std::str::FromStr;
+
+use ttf_parser::{Rect, Tag};
+use typst::eval::Bytes;
 use typst::font::{Font, FontStretch, FontStyle, FontWeight, VerticalFontMetric};
+use unicode_script::{Script, UnicodeScript};
 
 use crate::layout::ParElem;
 use crate::prelude::*;
@@ -26,6 +35,7 @@ pub(super) fn define(global: &mut Scope) {
     global.define("text", TextElem::func());
     global.define("linebreak", LinebreakElem::func());
     global.define("smartquote", SmartQuoteElem::func());
+    global.define("quote", QuoteElem::func());
     global.define("strong", StrongElem::func());
     global.define("emph", EmphElem::func());
     global.define("lower", lower_func());
@@ -33,8 +43,10 @@ pub(super) fn define(global: &mut Scope) {
     global.define("smallcaps", smallcaps_func());
     global.define("sub", SubElem::func());
     global.define("super", SuperElem::func());
+    global.define("ruby", RubyElem::func());
     global.define("underline", UnderlineElem::func());
     global.define("strike", StrikeElem::func());
+    global.define("highlight", HighlightElem::func());
     global.define("overline", OverlineElem::func());
     global.define("raw", RawElem::func());
     global.define("lorem", lorem_func());
@@ -77,6 +89,23 @@ pub struct TextElem {
     /// هذا عربي.
     ///
     /// ```
+    ///
+    /// Instead of a family name, an entry can also be a dictionary with the
+    /// keys `name` and `covers`. Such a family is only used for the characters
+    /// it covers, which are either given as an ISO 15924 script code or as a
+    /// unicode range like `{"U+0600-06FF"}`. A script also covers spaces and
+    /// punctuation in text of that script. This way, you can pick a font for
+    /// a script without affecting the rest of the text.
+    ///
+    /// ```example
+    /// #set text(font: (
+    ///   (name: "Noto Sans Arabic", covers: "arab"),
+    ///   "Inria Serif",
+    /// ))
+    ///
+    /// This is Latin. \
+    /// هذا عربي.
+    /// ```
     #[default(FontList(vec![FontFamily::new("Linux Libertine")]))]
     pub font: FontList,
 
@@ -84,11 +113,12 @@ pub struct TextElem {
     /// contains no match. This lets Typst search through all available fonts
     /// for the most similar one that has the necessary glyphs.
     ///
-    /// _Note:_ Currently, there are no warnings when fallback is disabled and
-    /// no glyphs are found. Instead, your text shows up in the form of "tofus":
-    /// Small boxes that indicate the lack of an appropriate glyph. In the
-    /// future, you will be able to instruct Typst to issue warnings so you know
-    /// something is up.
+    /// If you configured the font list and this fallback kicks in, Typst
+    /// issues a warning that names the font it fell back to. When no font has
+    /// the necessary glyphs, your text
+    /// shows up in the form of "tofus": Small boxes that indicate the lack of
+    /// an appropriate glyph. Typst warns about the affected characters in
+    /// this case, too.
     ///
     /// ```example
     /// #set text(font: "Inria Serif")
@@ -100,6 +130,23 @@ pub struct TextElem {
     #[default(true)]
     pub fallback: bool,
 
+    /// Which styles to synthesize when the selected font lacks them.
+    ///
+    /// When a family has no bold or italic face, Typst emboldens or slants
+    /// the regular one instead. When a font has no small capitals for
+    /// [`smallcaps`]($func/smallcaps), Typst scales down its capitals. Set this
+    /// to `{false}` to disable synthesis or to a dictionary with the keys
+    /// `bold`, `italic` and `smallcaps` to control it per style.
+    ///
+    /// ```example
+    /// #set text(font: "Ubuntu")
+    /// _Slanted_ and *emboldened* \
+    /// #set text(synthesis: false)
+    /// _Regular_ and *regular*
+    /// ```
+    #[fold]
+    pub synthesis: Synthesis,
+
     /// The desired font style.
     ///
     /// When an italic style is requested and only an oblique one is available,
@@ -233,6 +280,50 @@ pub struct TextElem {
     #[default(true)]
     pub overhang: bool,
 
+    /// How far individual characters hang into the margin when
+    /// [`overhang`]($func/text.overhang) is enabled, relative to their width.
+    ///
+    /// Given as a dictionary from characters to ratios. To tune the
+    /// protrusion for a specific font, map the font's family name to such a
+    /// dictionary instead. Characters that are not listed use built-in
+    /// defaults for common punctuation and dashes at the end of a line.
+    /// Listed characters also protrude at the start of a line, which is useful
+    /// for opening quotation marks.
+    ///
+    /// ```example
+    /// #set par(justify: true)
+    /// #set text(protrusion: (
+    ///   "-": 100%,
+    ///   "linux libertine": ("“": 50%),
+    /// ))
+    /// “This justified text has a hyphen in
+    /// the paragraph's first line. Hanging
+    /// the hyphen fully into the margin.”
+    /// ```
+    #[fold]
+    pub protrusion: Protrusion,
+
+    /// How much the glyphs of justified text may be scaled horizontally to
+    /// improve the spacing.
+    ///
+    /// With font expansion, a justified line first scales its glyphs by up to
+    /// this ratio before adjusting the spaces, which leads to more even word
+    /// spacing. Small values like `{2%}` are typically unnoticeable.
+    ///
+    /// Given either as a ratio for all fonts or as a dictionary from font
+    /// family names to ratios. Fonts that are not listed in the dictionary
+    /// are not expanded.
+    ///
+    /// ```example
+    /// #set par(justify: true)
+    /// #set text(expansion: ("linux libertine": 2%))
+    /// With font expansion, justified text
+    /// needs less variation in the spacing
+    /// between its words.
+    /// ```
+    #[default(FontRatio::All(Ratio::zero()))]
+    pub expansion: FontRatio,
+
     /// The top end of the conceptual frame around the text used for layout and
     /// positioning. This affects the size of containers that hold text.
     ///
@@ -324,6 +415,8 @@ pub struct TextElem {
     /// - `{auto}`: Automatically infer the direction from the `lang` property.
     /// - `{ltr}`: Layout text from left to right.
     /// - `{rtl}`: Layout text from right to left.
+    /// - `{ttb}`: Layout text from top to bottom in columns that progress from
+    ///   right to left, as in vertical Chinese and Japanese text.
     ///
     /// When writing in right-to-left scripts like Arabic or Hebrew, you should
     /// set the [text language]($func/text.lang) or direction. While individual
@@ -343,6 +436,18 @@ pub struct TextElem {
     /// #set text(dir: rtl)
     /// هذا عربي.
     /// ```
+    ///
+    /// In vertical text, Chinese and Japanese characters stand upright and use
+    /// the font's vertical glyph forms, while other text is rotated by a
+    /// quarter turn. A vertical paragraph fills the height of its region and
+    /// its lines are broken into multiple frames if they don't fit the
+    /// region's width.
+    ///
+    /// ```example
+    /// #set page(width: 80pt, height: 100pt)
+    /// #set text(dir: ttb, lang: "ja", font: "Noto Serif CJK JP")
+    /// 吾輩は猫である。名前はまだ無い。Typstで組版。
+    /// ```
     #[resolve]
     pub dir: TextDir,
 
@@ -368,6 +473,53 @@ pub struct TextElem {
     #[resolve]
     pub hyphenate: Hyphenate,
 
+    /// Additional TeX-style hyphenation pattern files, given as a dictionary
+    /// from language codes to file paths.
+    ///
+    /// The files may contain `\patterns{..}` and `\hyphenation{..}` blocks.
+    /// If they contain neither, the whole file is read as a list of patterns.
+    /// Registered patterns take precedence over the built-in ones and also
+    /// enable hyphenation for languages that Typst has no patterns for.
+    ///
+    /// ```typ
+    /// #set text(
+    ///   lang: "cy",
+    ///   hyphenate: true,
+    ///   hyphenation-patterns: (cy: "hyph-cy.tex"),
+    /// )
+    /// ```
+    #[parse(
+        let (patterns, patterns_data) = parse_hyphenation_patterns(vm, args)?;
+        patterns
+    )]
+    #[fold]
+    pub hyphenation_patterns: HyphenationPatterns,
+
+    /// The raw file buffers of the hyphenation pattern files.
+    #[internal]
+    #[parse(patterns_data)]
+    #[fold]
+    pub hyphenation_patterns_data: Vec<Bytes>,
+
+    /// Words with explicit hyphenation points, given as a dictionary from
+    /// language codes to arrays of words.
+    ///
+    /// Hyphens in a word mark where it may be broken. A word without hyphens
+    /// is never hyphenated. Exceptions take precedence over all patterns and
+    /// are matched case-insensitively.
+    ///
+    /// ```example
+    /// #set page(width: 80pt)
+    /// #set par(justify: true)
+    /// #set text(hyphenation-exceptions: (
+    ///   en: ("data-base", "Typst"),
+    /// ))
+    ///
+    /// A database written in Typst.
+    /// ```
+    #[fold]
+    pub hyphenation_exceptions: HyphenationExceptions,
+
     /// Whether to apply kerning.
     ///
     /// When enabled, specific letter pairings move closer together or further
@@ -386,6 +538,37 @@ pub struct TextElem {
     #[default(true)]
     pub kerning: bool,
 
+    /// Whether to insert a quarter em of space between Chinese or Japanese
+    /// characters and adjacent Latin letters or numbers.
+    ///
+    /// The space is removed at the start and end of a line.
+    ///
+    /// ```example
+    /// #set text(font: ("Linux Libertine", "Noto Serif CJK SC"))
+    /// 第4章介绍Typst。 \
+    /// #set text(cjk-latin-spacing: false)
+    /// 第4章介绍Typst。
+    /// ```
+    #[default(true)]
+    pub cjk_latin_spacing: bool,
+
+    /// Whether to compress consecutive Chinese and Japanese punctuation marks,
+    /// for example a closing bracket followed by a full stop.
+    ///
+    /// Like other punctuation width adjustments, this only applies to the
+    /// punctuation style of Simplified Chinese and Japanese. In Traditional
+    /// Chinese (`{"zh"}` with region `{"TW"}` or `{"HK"}`), the marks keep
+    /// their full width.
+    ///
+    /// ```example
+    /// #set text(font: "Noto Serif CJK SC", lang: "zh")
+    /// 他说：「你好。」。 \
+    /// #set text(compress-punctuation: false)
+    /// 他说：「你好。」。
+    /// ```
+    #[default(true)]
+    pub compress_punctuation: bool,
+
     /// Whether to apply stylistic alternates.
     ///
     /// Sometimes fonts contain alternative glyphs for the same codepoint.
@@ -506,6 +689,24 @@ pub struct TextElem {
     #[fold]
     pub features: FontFeatures,
 
+    /// Values for the variation axes of variable fonts.
+    ///
+    /// Given as a dictionary that maps four-letter axis tags to numbers. Next
+    /// to the registered axes like `wght` (weight), `wdth` (width), `opsz`
+    /// (optical size), `ital` and `slnt`, this also works with a font's
+    /// custom axes. Unless set here, the `wght` and `wdth` axes follow the
+    /// text's [weight]($func/text.weight) and [stretch]($func/text.stretch)
+    /// while `opsz` stays at the font's default. Fonts without an axis ignore
+    /// its value.
+    ///
+    /// ```typ
+    /// #set text(font: "Inter Variable")
+    /// #text(variations: (wght: 250))[Light] \
+    /// #text(variations: (opsz: 32))[Display]
+    /// ```
+    #[fold]
+    pub variations: FontVariations,
+
     /// Content in which all text is styled according to the other arguments.
     #[external]
     #[required]
@@ -540,6 +741,79 @@ pub struct TextElem {
     #[internal]
     #[default(false)]
     pub smallcaps: bool,
+
+    /// Whether the text is set in vertical lines. Set by the paragraph
+    /// layout for text with a top-to-bottom direction.
+    #[internal]
+    #[default(false)]
+    pub vertical: bool,
+}
+
+/// Which styles to synthesize when a font lacks them.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Synthesis {
+    /// Whether to embolden the outlines for bold text.
+    pub bold: Option<bool>,
+    /// Whether to slant the outlines for italic and oblique text.
+    pub italic: Option<bool>,
+    /// Whether to scale down capitals for small capitals.
+    pub smallcaps: Option<bool>,
+}
+
+impl Synthesis {
+    /// Whether to synthesize bold.
+    pub fn bold(&self) -> bool {
+        self.bold.unwrap_or(true)
+    }
+
+    /// Whether to synthesize italics.
+    pub fn italic(&self) -> bool {
+        self.italic.unwrap_or(true)
+    }
+
+    /// Whether to synthesize small capitals.
+    pub fn smallcaps(&self) -> bool {
+        self.smallcaps.unwrap_or(true)
+    }
+}
+
+impl Fold for Synthesis {
+    type Output = Self;
+
+    fn fold(self, outer: Self::Output) -> Self::Output {
+        Self {
+            bold: self.bold.or(outer.bold),
+            italic: self.italic.or(outer.italic),
+            smallcaps: self.smallcaps.or(outer.smallcaps),
+        }
+    }
+}
+
+cast! {
+    Synthesis,
+    self => {
+        let mut dict = Dict::new();
+        let mut handle = |key: &str, enabled: Option<bool>| {
+            if let Some(enabled) = enabled {
+                dict.insert(key.into(), enabled.into_value());
+            }
+        };
+
+        handle("bold", self.bold);
+        handle("italic", self.italic);
+        handle("smallcaps", self.smallcaps);
+
+        Value::Dict(dict)
+    },
+    v: bool => Self { bold: Some(v), italic: Some(v), smallcaps: Some(v) },
+    mut dict: Dict => {
+        let mut take = |key| dict.take(key).ok().map(Value::cast).transpose();
+        let bold = take("bold")?;
+        let italic = take("italic")?;
+        let smallcaps = take("smallcaps")?;
+        dict.finish(&["bold", "italic", "smallcaps"])?;
+        Self { bold, italic, smallcaps }
+    },
 }
 
 impl TextElem {
@@ -568,30 +842,131 @@ impl PlainText for TextElem {
 
 /// A lowercased font family like "arial".
 #[derive(Clone, Eq, PartialEq, Hash)]
-pub struct FontFamily(EcoString);
+pub struct FontFamily {
+    name: EcoString,
+    covers: Option<Covers>,
+}
 
 impl FontFamily {
     /// Create a named font family variant.
     pub fn new(string: &str) -> Self {
-        Self(string.to_lowercase().into())
+        Self::with_covers(string, None)
+    }
+
+    /// Create a named font family variant that is only used for the
+    /// characters it covers.
+    pub fn with_covers(string: &str, covers: Option<Covers>) -> Self {
+        Self { name: string.to_lowercase().into(), covers }
     }
 
     /// The lowercased family name.
     pub fn as_str(&self) -> &str {
-        &self.0
+        &self.name
+    }
+
+    /// Which characters the family is used for. If `None`, it is used for
+    /// all characters.
+    pub fn covers(&self) -> Option<&Covers> {
+        self.covers.as_ref()
     }
 }
 
 impl Debug for FontFamily {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        self.0.fmt(f)
+        self.name.fmt(f)
     }
 }
 
 cast! {
     FontFamily,
-    self => self.0.into_value(),
+    self => match self.covers {
+        Some(covers) => {
+            let mut dict = Dict::new();
+            dict.insert("name".into(), self.name.into_value());
+            dict.insert("covers".into(), covers.into_value());
+            Value::Dict(dict)
+        }
+        None => self.name.into_value(),
+    },
     string: EcoString => Self::new(&string),
+    mut dict: Dict => {
+        let name = dict.take("name")?.cast::<EcoString>()?;
+        let covers = dict.take("covers").ok().map(Value::cast).transpose()?;
+        dict.finish(&["name", "covers"])?;
+        Self::with_covers(&name, covers)
+    },
+}
+
+/// The characters a font family is used for.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Covers {
+    /// The characters of a writing script. In text that contains the script,
+    /// this also includes script-neutral characters like punctuation.
+    Script(Script),
+    /// An inclusive range of code points.
+    Range(char, char),
+}
+
+impl Covers {
+    /// Whether the family may be used for some of the characters in the text.
+    pub fn applies_to(&self, text: &str) -> bool {
+        text.chars().any(|c| match *self {
+            Self::Script(script) => c.script() == script,
+            Self::Range(start, end) => (start..=end).contains(&c),
+        })
+    }
+
+    /// Whether the family may be used for the character, assuming that it
+    /// [applies](Self::applies_to) to the surrounding text.
+    pub fn contains(&self, c: char) -> bool {
+        use Script::*;
+        match *self {
+            Self::Script(script) => {
+                matches!(c.script(), Common | Inherited) || c.script() == script
+            }
+            Self::Range(start, end) => (start..=end).contains(&c),
+        }
+    }
+}
+
+impl FromStr for Covers {
+    type Err = &'static str;
+
+    /// Parse an ISO 15924 script code or a unicode range like `U+0600-06FF`.
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let Some(range) = string.strip_prefix("U+").or_else(|| string.strip_prefix("u+"))
+        else {
+            let mut code = string.to_ascii_lowercase();
+            if let Some(first) = code.get_mut(..1) {
+                first.make_ascii_uppercase();
+            }
+            return Script::from_short_name(&code)
+                .map(Self::Script)
+                .ok_or("expected ISO 15924 script code or unicode range");
+        };
+
+        let parse = |hex: &str| {
+            let hex = hex.trim_start_matches(['U', 'u', '+']);
+            u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+        };
+
+        let (start, end) = range.split_once('-').unwrap_or((range, range));
+        match (parse(start), parse(end)) {
+            (Some(start), Some(end)) if start <= end => Ok(Self::Range(start, end)),
+            _ => Err("invalid unicode range"),
+        }
+    }
+}
+
+cast! {
+    Covers,
+    self => match self {
+        Self::Script(script) => script.short_name().to_lowercase().into_value(),
+        Self::Range(start, end) => {
+            eco_format!("U+{:04X}-{:04X}", start as u32, end as u32).into_value()
+        }
+    },
+    string: EcoString => Self::from_str(&string)?,
 }
 
 /// Font family fallback list.
@@ -610,7 +985,7 @@ impl IntoIterator for FontList {
 cast! {
     FontList,
     self => if self.0.len() == 1 {
-        self.0.into_iter().next().unwrap().0.into_value()
+        self.0.into_iter().next().unwrap().into_value()
     } else {
         self.0.into_value()
     },
@@ -778,8 +1153,8 @@ cast! {
     TextDir,
     self => self.0.into_value(),
     v: Smart<Dir> => {
-        if v.map_or(false, |dir| dir.axis() == Axis::Y) {
-            bail!("text direction must be horizontal");
+        if v == Smart::Custom(Dir::BTT) {
+            bail!("text direction cannot be bottom-to-top");
         }
         Self(v)
     },
@@ -902,3 +1277,164 @@ impl Fold for FontFeatures {
         self
     }
 }
+
+/// Values for the variation axes of variable fonts.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Hash)]
+pub struct FontVariations(pub Vec<([u8; 4], Scalar)>);
+
+cast! {
+    FontVariations,
+    self => self.0
+        .into_iter()
+        .map(|(tag, value)| {
+            let key = std::str::from_utf8(&tag).unwrap_or_default();
+            (key.into(), value.0.into_value())
+        })
+        .collect::<Dict>()
+        .into_value(),
+    values: Dict => Self(values
+        .into_iter()
+        .map(|(k, v)| {
+            let Ok(tag) = <[u8; 4]>::try_from(k.as_bytes()) else {
+                bail!("expected four-letter axis tag");
+            };
+            Ok((tag, Scalar(v.cast::<f64>()?)))
+        })
+        .collect::<StrResult<_>>()?),
+}
+
+impl Fold for FontVariations {
+    type Output = Self;
+
+    fn fold(mut self, outer: Self::Output) -> Self::Output {
+        // Inner values come first and must take precedence, so they are
+        // applied last.
+        let mut values = outer.0;
+        values.append(&mut self.0);
+        self.0 = values;
+        self
+    }
+}
+
+/// Per-character protrusion amounts for hanging punctuation, either for all
+/// fonts or for a specific font family.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Hash)]
+pub struct Protrusion(pub Vec<(Option<EcoString>, char, Ratio)>);
+
+impl Protrusion {
+    /// The configured protrusion of a character in a font, if any. Settings
+    /// for the font's family take precedence over those for all fonts.
+    pub fn get(&self, font: &Font, c: char) -> Option<Ratio> {
+        let family = font.info().family.as_str();
+        let find = |specific: bool| {
+            self.0
+                .iter()
+                .find(|(f, k, _)| {
+                    *k == c
+                        && match f {
+                            Some(f) => specific && f.eq_ignore_ascii_case(family),
+                            None => !specific,
+                        }
+                })
+                .map(|&(_, _, v)| v)
+        };
+        find(true).or_else(|| find(false))
+    }
+}
+
+cast! {
+    Protrusion,
+    self => {
+        let mut dict = Dict::new();
+        for (family, c, amount) in self.0.into_iter().rev() {
+            match family {
+                Some(family) => {
+                    let mut inner = dict
+                        .take(&family)
+                        .ok()
+                        .and_then(|v| v.cast::<Dict>().ok())
+                        .unwrap_or_default();
+                    inner.insert(c.into(), amount.into_value());
+                    dict.insert(family.into(), inner.into_value());
+                }
+                None => dict.insert(c.into(), amount.into_value()),
+            }
+        }
+        dict.into_value()
+    },
+    values: Dict => {
+        let mut entries = vec![];
+        for (k, v) in values {
+            if let Value::Dict(inner) = v {
+                let family = EcoString::from(k.to_lowercase());
+                for (k, v) in inner {
+                    entries.push((Some(family.clone()), protruding(&k)?, v.cast()?));
+                }
+            } else {
+                entries.push((None, protruding(&k)?, v.cast()?));
+            }
+        }
+        Self(entries)
+    },
+}
+
+/// Parse a protrusion key into a single character.
+fn protruding(key: &str) -> StrResult<char> {
+    let mut chars = key.chars();
+    let (Some(c), None) = (chars.next(), chars.next()) else {
+        bail!("expected single characters or font families as keys");
+    };
+    Ok(c)
+}
+
+impl Fold for Protrusion {
+    type Output = Self;
+
+    fn fold(mut self, outer: Self::Output) -> Self::Output {
+        self.0.extend(outer.0);
+        self
+    }
+}
+
+/// A ratio for all fonts or for individual font families.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum FontRatio {
+    /// The ratio applies to all fonts.
+    All(Ratio),
+    /// The ratios apply to the listed lowercased font families. Other fonts
+    /// use zero.
+    Families(Vec<(EcoString, Ratio)>),
+}
+
+impl FontRatio {
+    /// The ratio for a font.
+    pub fn get(&self, font: &Font) -> Ratio {
+        match self {
+            Self::All(ratio) => *ratio,
+            Self::Families(families) => {
+                let family = font.info().family.as_str();
+                families
+                    .iter()
+                    .find(|(f, _)| f.eq_ignore_ascii_case(family))
+                    .map_or(Ratio::zero(), |&(_, ratio)| ratio)
+            }
+        }
+    }
+}
+
+cast! {
+    FontRatio,
+    self => match self {
+        Self::All(ratio) => ratio.into_value(),
+        Self::Families(families) => families
+            .into_iter()
+            .map(|(family, ratio)| (family.into(), ratio.into_value()))
+            .collect::<Dict>()
+            .into_value(),
+    },
+    v: Ratio => Self::All(v),
+    values: Dict => Self::Families(values
+        .into_iter()
+        .map(|(k, v)| Ok((k.to_lowercase().into(), v.cast()?)))
+        .collect::<StrResult<_>>()?),
+}
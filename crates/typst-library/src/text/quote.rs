@@ -0,0 +1,167 @@
+use super::{Quotes, SmartQuoteElem, SpaceElem, TextElem};
+use crate::layout::{BlockElem, PadElem, Spacing, VElem};
+use crate::meta::CiteElem;
+use crate::prelude::*;
+
+/// Displays a quote alongside an optional attribution.
+///
+/// Inline quotes are wrapped in the quotation marks of the current
+/// [text language]($func/text.lang). Block quotes are set apart from the
+/// surrounding text and indented.
+///
+/// ## Example { #example }
+/// ```example
+/// Socrates famously said
+/// #quote[I know that I know nothing].
+///
+/// #quote(block: true, attribution: [Plato])[
+///   I seem, then, in just this little
+///   thing to be wiser than this man.
+/// ]
+/// ```
+///
+/// Display: Quote
+/// Category: text
+#[element(Show)]
+pub struct QuoteElem {
+    /// Whether this is a block quote.
+    ///
+    /// ```example
+    /// An inline quote looks like
+    /// #quote[cogito, ergo sum], and
+    /// a block quote like this:
+    /// #quote(
+    ///   block: true,
+    ///   attribution: [JFK],
+    /// )[Ich bin ein Berliner.]
+    /// ```
+    pub block: bool,
+
+    /// Whether to wrap the quote in quotation marks.
+    ///
+    /// The marks depend on the [text language]($func/text.lang) and region
+    /// and on whether [alternative smart quotes]($func/smartquote.alternative)
+    /// are enabled. Nested quotes alternate between double and single marks.
+    /// When `{auto}`, inline quotes are wrapped and block quotes are not.
+    ///
+    /// ```example
+    /// #set text(lang: "de")
+    /// #quote[Er sagte #quote[Hallo].]
+    ///
+    /// #set text(lang: "fr")
+    /// #quote[Il a dit #quote[Bonjour].]
+    /// ```
+    pub quotes: Smart<bool>,
+
+    /// The attribution of the quote, usually its author or source.
+    ///
+    /// This can be any content or a label of a bibliography entry, which is
+    /// then [cited]($func/cite). Block quotes show the attribution below the
+    /// quote. Inline quotes only show a citation for a label.
+    ///
+    /// ```example
+    /// #quote(block: true, attribution: <tolkien54>)[
+    ///   You cannot pass.
+    /// ]
+    ///
+    /// #bibliography("works.bib")
+    /// ```
+    pub attribution: Option<Attribution>,
+
+    /// The quote.
+    #[required]
+    pub body: Content,
+
+    /// How deeply this quote is nested in other quotes.
+    #[internal]
+    #[fold]
+    depth: QuoteDepth,
+}
+
+impl Show for QuoteElem {
+    #[tracing::instrument(name = "QuoteElem::show", skip_all)]
+    fn show(&self, _: &mut Vt, styles: StyleChain) -> SourceResult<Content> {
+        let mut realized = self.body();
+        let block = self.block(styles);
+
+        if self.quotes(styles).unwrap_or(!block) {
+            let quotes = Quotes::from_lang(
+                TextElem::lang_in(styles),
+                TextElem::region_in(styles),
+                SmartQuoteElem::alternative_in(styles),
+            );
+
+            // Nested quotes alternate between double and single quotes.
+            let double = self.depth(styles) % 2 == 0;
+            realized = Content::sequence([
+                TextElem::packed(quotes.open(double)),
+                realized.styled(Self::set_depth(QuoteDepth)),
+                TextElem::packed(quotes.close(double)),
+            ]);
+        }
+
+        if block {
+            realized = BlockElem::new().with_body(Some(realized)).pack();
+
+            if let Some(attribution) = self.attribution(styles) {
+                let attribution = match attribution {
+                    Attribution::Content(content) => content,
+                    Attribution::Label(label) => {
+                        CiteElem::new(vec![label.0]).with_brackets(false).pack()
+                    }
+                };
+
+                // Bring the attribution closer to the quote than the
+                // regular block spacing would.
+                let spacing = VElem::weak(Spacing::Rel(Em::new(0.9).into())).pack();
+                let line = Content::sequence([
+                    TextElem::packed('—'),
+                    SpaceElem::new().pack(),
+                    attribution,
+                ]);
+                realized += spacing + line.aligned(Axes::with_x(Some(GenAlign::End)));
+            }
+
+            let x = Em::new(1.0).into();
+            realized = PadElem::new(realized).with_left(x).with_right(x).pack();
+        } else if let Some(Attribution::Label(label)) = self.attribution(styles) {
+            realized += SpaceElem::new().pack() + CiteElem::new(vec![label.0]).pack();
+        }
+
+        Ok(realized)
+    }
+}
+
+/// The attribution of a [quote](QuoteElem).
+#[derive(Debug, Clone, Hash)]
+pub enum Attribution {
+    Content(Content),
+    Label(Label),
+}
+
+cast! {
+    Attribution,
+    self => match self {
+        Self::Content(content) => content.into_value(),
+        Self::Label(label) => label.into_value(),
+    },
+    label: Label => Self::Label(label),
+    content: Content => Self::Content(content),
+}
+
+/// The nesting depth of a quote.
+struct QuoteDepth;
+
+cast! {
+    QuoteDepth,
+    self => Value::None,
+    _: Value => Self,
+}
+
+impl Fold for QuoteDepth {
+    type Output = usize;
+
+    fn fold(self, outer: Self::Output) -> Self::Output {
+        outer + 1
+    }
+}
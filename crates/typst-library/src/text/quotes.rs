@@ -181,7 +181,7 @@ impl<'s> Quotes<'s> {
     }
 
     /// The opening quote.
-    fn open(&self, double: bool) -> &'s str {
+    pub fn open(&self, double: bool) -> &'s str {
         if double {
             self.double_open
         } else {
@@ -190,7 +190,7 @@ impl<'s> Quotes<'s> {
     }
 
     /// The closing quote.
-    fn close(&self, double: bool) -> &'s str {
+    pub fn close(&self, double: bool) -> &'s str {
         if double {
             self.double_close
         } else {
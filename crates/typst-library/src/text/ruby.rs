@@ -0,0 +1,234 @@
+use super::{SpaceElem, TextElem, TextSize};
+use crate::layout::BoxElem;
+use crate::prelude::*;
+
+/// Annotates text with ruby, like Japanese furigana or Chinese bopomofo.
+///
+/// The annotation is set in smaller text above its base. Ruby lays out
+/// inline: It only increases the height of the lines that contain it and lines
+/// can break between its groups.
+///
+/// ## Example { #example }
+/// ```example
+/// #set text(font: "Noto Serif CJK JP", lang: "ja")
+/// #ruby[東京][とうきょう]に
+/// #ruby(mode: "mono")[漢字][かん|じ]を
+/// #ruby(mode: "jukugo")[明日][あ|した]書く。
+/// ```
+///
+/// Display: Ruby
+/// Category: text
+#[element(Show)]
+pub struct RubyElem {
+    /// How the annotation is distributed over the base.
+    ///
+    /// For `{"mono"}` and `{"jukugo"}` ruby, the base and the annotation must
+    /// be plain text and the annotation is split into one part per base
+    /// character at the [`delimiter`]($func/ruby.delimiter).
+    #[default(RubyMode::Group)]
+    pub mode: RubyMode,
+
+    /// The delimiter between the annotations of the individual base
+    /// characters in `{"mono"}` and `{"jukugo"}` ruby. An annotation without
+    /// a delimiter is treated as group ruby.
+    #[default("|".into())]
+    pub delimiter: Str,
+
+    /// The font size of the annotation.
+    #[default(TextSize(Em::new(0.5).into()))]
+    pub size: TextSize,
+
+    /// The gap between the base and its annotation.
+    #[resolve]
+    pub gap: Length,
+
+    /// How far an annotation that is wider than its base may extend over the
+    /// adjacent text on each side without widening the base. Next to other
+    /// ruby and at the start or end of a line, the base is widened instead.
+    ///
+    /// ```example
+    /// #set text(font: "Noto Serif CJK JP")
+    /// #ruby(overhang: 0pt)[嘘][うそ]だ \
+    /// #ruby[嘘][うそ]だ
+    /// ```
+    #[resolve]
+    #[default(Em::new(0.25).into())]
+    pub overhang: Length,
+
+    /// The text to annotate.
+    #[required]
+    pub body: Content,
+
+    /// The annotation.
+    #[required]
+    pub annotation: Content,
+}
+
+impl Show for RubyElem {
+    #[tracing::instrument(name = "RubyElem::show", skip_all)]
+    fn show(&self, vt: &mut Vt, styles: StyleChain) -> SourceResult<Content> {
+        let body = self.body();
+        let annotation = self.annotation().styled(TextElem::set_size(self.size(styles)));
+        let group = |base: Content, annotation: Content| {
+            BoxElem::new()
+                .with_body(Some(RubyGroup::new(base, annotation).pack()))
+                .pack()
+        };
+
+        let mode = self.mode(styles);
+        if mode == RubyMode::Group {
+            return Ok(group(body, annotation));
+        }
+
+        // Split mono and jukugo ruby into one part per base character.
+        let delimiter = self.delimiter(styles);
+        let (Some(base), Some(text)) = (plain(&body), plain(&self.annotation())) else {
+            bail!(self.span(), "{} ruby requires plain text", mode.as_str());
+        };
+
+        if !text.contains(delimiter.as_str()) {
+            return Ok(group(body, annotation));
+        }
+
+        let chars: Vec<char> = base.chars().collect();
+        let parts: Vec<&str> = text.split(delimiter.as_str()).collect();
+        if chars.len() != parts.len() {
+            bail!(
+                self.span(),
+                "ruby annotation has {} parts, but its base has {} characters",
+                parts.len(),
+                chars.len()
+            );
+        }
+
+        let size = self.size(styles);
+        let styled = |part: &str| TextElem::packed(part).styled(TextElem::set_size(size));
+
+        // Jukugo ruby is only split if each part fits over its character.
+        // Otherwise, the word is annotated as a whole.
+        if mode == RubyMode::Jukugo {
+            let pod = Regions::one(Size::splat(Abs::inf()), Axes::splat(false));
+            let mut fits = true;
+            for (&c, part) in chars.iter().zip(&parts) {
+                let base = TextElem::packed(c).layout(vt, styles, pod)?.into_frame();
+                let part = styled(part).layout(vt, styles, pod)?.into_frame();
+                fits &= part.width() <= base.width();
+            }
+
+            if !fits {
+                return Ok(group(body, styled(&parts.concat())));
+            }
+        }
+
+        Ok(Content::sequence(
+            chars
+                .iter()
+                .zip(parts)
+                .map(|(&c, part)| group(TextElem::packed(c), styled(part))),
+        ))
+    }
+}
+
+/// How a ruby annotation is distributed over its base.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Cast)]
+pub enum RubyMode {
+    /// Each base character has its own annotation. Lines can break between
+    /// the characters.
+    Mono,
+    /// The annotation is centered over the whole base, which is kept on one
+    /// line.
+    Group,
+    /// Like mono ruby if each annotation fits over its character, otherwise
+    /// like group ruby. This is the traditional way to annotate compound words
+    /// in Japanese.
+    Jukugo,
+}
+
+impl RubyMode {
+    /// The name of the mode.
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Mono => "mono",
+            Self::Group => "group",
+            Self::Jukugo => "jukugo",
+        }
+    }
+}
+
+/// A base with its annotation that is laid out as a unit.
+///
+/// Display: Ruby Group
+/// Category: special
+#[element(Layout)]
+pub struct RubyGroup {
+    /// The annotated text.
+    #[required]
+    pub body: Content,
+
+    /// The annotation.
+    #[required]
+    pub annotation: Content,
+}
+
+impl RubyGroup {
+    /// Layout the group as part of a paragraph.
+    ///
+    /// The frame is as wide as the wider of base and annotation. Also returns
+    /// how far a wider annotation may overhang the adjacent text on each side.
+    /// The paragraph only lets it do so next to text, so that annotations
+    /// neither overlap each other nor reach into the margin.
+    pub fn layout_inline(
+        &self,
+        vt: &mut Vt,
+        styles: StyleChain,
+        regions: Regions,
+    ) -> SourceResult<(Frame, Abs)> {
+        let pod = Regions::one(regions.base(), Axes::splat(false));
+        let base = self.body().layout(vt, styles, pod)?.into_frame();
+        let annotation = self.annotation().layout(vt, styles, pod)?.into_frame();
+        let gap = RubyElem::gap_in(styles);
+        let overhang = ((annotation.width() - base.width()) / 2.0)
+            .min(RubyElem::overhang_in(styles))
+            .max(Abs::zero());
+
+        // The annotation is centered over the base.
+        let width = base.width().max(annotation.width());
+        let top = annotation.height() + gap;
+        let mut frame = Frame::new(Size::new(width, top + base.height()));
+        frame.set_baseline(top + base.baseline());
+        frame.push_frame(Point::with_x((width - annotation.width()) / 2.0), annotation);
+        frame.push_frame(Point::new((width - base.width()) / 2.0, top), base);
+
+        Ok((frame, overhang))
+    }
+}
+
+impl Layout for RubyGroup {
+    #[tracing::instrument(name = "RubyGroup::layout", skip_all)]
+    fn layout(
+        &self,
+        vt: &mut Vt,
+        styles: StyleChain,
+        regions: Regions,
+    ) -> SourceResult<Fragment> {
+        let (frame, _) = self.layout_inline(vt, styles, regions)?;
+        Ok(Fragment::frame(frame))
+    }
+}
+
+/// Extract the text of content that consists of text and spaces only.
+fn plain(content: &Content) -> Option<EcoString> {
+    if content.is::<SpaceElem>() {
+        Some(' '.into())
+    } else if let Some(elem) = content.to::<TextElem>() {
+        Some(elem.text())
+    } else if let Some(children) = content.to_sequence() {
+        let mut full = EcoString::new();
+        for child in children {
+            full.push_str(&plain(child)?);
+        }
+        Some(full)
+    } else {
+        None
+    }
+}
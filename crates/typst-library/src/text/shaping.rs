@@ -1,14 +1,17 @@
 use std::borrow::Cow;
 use std::ops::Range;
 use std::str::FromStr;
+use std::sync::Arc;
 
 use az::SaturatingAs;
-use rustybuzz::{Feature, Tag, UnicodeBuffer};
-use typst::font::{Font, FontStyle, FontVariant};
+use rustybuzz::{Feature, UnicodeBuffer};
+use ttf_parser::Tag;
+use typst::diag::warning;
+use typst::font::{Font, FontStyle, FontVariant, FontVariation, FontWeight};
 use typst::util::SliceExt;
 use unicode_script::{Script, UnicodeScript};
 
-use super::{decorate, FontFamily, NumberType, NumberWidth, TextElem};
+use super::{decorate, FontFamily, FontList, NumberType, NumberWidth, TextElem};
 use crate::layout::SpanMapper;
 use crate::prelude::*;
 
@@ -17,6 +20,7 @@ use crate::prelude::*;
 /// This type contains owned or borrowed shaped text runs, which can be
 /// measured, used to reshape substrings more quickly and converted into a
 /// frame.
+#[derive(Clone)]
 pub struct ShapedText<'a> {
     /// The start of the text in the full paragraph.
     pub base: usize,
@@ -71,6 +75,24 @@ pub struct ShapedGlyph {
     pub safe_to_break: bool,
     /// The first char in this glyph's cluster.
     pub c: char,
+    /// The extra space before and after the glyph that separates it from
+    /// adjacent Latin text. It is included in the advance and offset.
+    pub cjk_latin_spacing: (Em, Em),
+    /// Whether a kashida may be inserted after this glyph's cluster in
+    /// logical order to justify Arabic text. Only set on the leftmost glyph
+    /// of a cluster.
+    pub kashida: bool,
+    /// Whether extra space may be inserted after this glyph's cluster to
+    /// justify text in a script without word separators. Only set on the
+    /// last glyph of a cluster.
+    pub inter_cluster: bool,
+    /// Whether the glyph stands upright in vertical text. Then, its advance
+    /// and `x_offset` run along the vertical line and its `y_offset` points
+    /// to the right.
+    pub upright: bool,
+    /// Whether the glyph is a synthesized small capital, that is, a capital
+    /// set at a smaller size. Its advance and offsets are already scaled.
+    pub small_cap: bool,
     /// The source code location of the glyph and its byte offset within it.
     pub span: (Span, u16),
 }
@@ -93,6 +115,7 @@ impl ShapedGlyph {
     pub fn is_justifiable(&self) -> bool {
         // GB style is not relevant here.
         self.is_space()
+            || self.inter_cluster
             || self.is_cjk_script()
             || self.is_cjk_left_aligned_punctuation(true)
             || self.is_cjk_right_aligned_punctuation()
@@ -105,6 +128,19 @@ impl ShapedGlyph {
         matches!(self.c.script(), Hiragana | Katakana | Han) || self.c == '\u{30FC}'
     }
 
+    /// Whether the glyph is a Latin, Greek or Cyrillic letter or a digit.
+    pub fn is_letter_or_number(&self) -> bool {
+        // Fullwidth letters and digits already have CJK spacing.
+        if matches!(self.c, '\u{FF00}'..='\u{FFEF}') {
+            return false;
+        }
+
+        use Script::*;
+        self.c.is_ascii_digit()
+            || (self.c.is_alphanumeric()
+                && matches!(self.c.script(), Latin | Greek | Cyrillic))
+    }
+
     pub fn is_cjk_punctuation(&self) -> bool {
         self.is_cjk_left_aligned_punctuation(true)
             || self.is_cjk_right_aligned_punctuation()
@@ -203,6 +239,63 @@ impl ShapedGlyph {
         self.adjustability.shrinkability.1 -= amount;
         self.adjustability.stretchability.1 += amount;
     }
+
+    /// Add CJK-Latin spacing before the glyph.
+    pub fn add_spacing_left(&mut self, amount: Em) {
+        self.x_offset += amount;
+        self.x_advance += amount;
+        self.cjk_latin_spacing.0 += amount;
+    }
+
+    /// Add CJK-Latin spacing after the glyph.
+    pub fn add_spacing_right(&mut self, amount: Em) {
+        self.x_advance += amount;
+        self.cjk_latin_spacing.1 += amount;
+    }
+
+    /// Remove the CJK-Latin spacing before the glyph and return its amount.
+    pub fn trim_spacing_left(&mut self) -> Em {
+        let amount = std::mem::take(&mut self.cjk_latin_spacing.0);
+        self.x_offset -= amount;
+        self.x_advance -= amount;
+        amount
+    }
+
+    /// Remove the CJK-Latin spacing after the glyph and return its amount.
+    pub fn trim_spacing_right(&mut self) -> Em {
+        let amount = std::mem::take(&mut self.cjk_latin_spacing.1);
+        self.x_advance -= amount;
+        amount
+    }
+}
+
+/// Add a quarter em of space between two adjacent glyphs if one of them is a
+/// CJK character and the other one a Latin letter or number. The space is
+/// attached to the CJK character.
+pub fn add_cjk_latin_spacing(glyph: &mut ShapedGlyph, next: &mut ShapedGlyph) {
+    let amount = Em::new(0.25);
+    if glyph.is_cjk_script() && next.is_letter_or_number() {
+        glyph.add_spacing_right(amount);
+    } else if glyph.is_letter_or_number() && next.is_cjk_script() {
+        next.add_spacing_left(amount);
+    }
+}
+
+/// Compress two consecutive CJK punctuation marks, as specified in Chinese
+/// Layout Requirements, section 3.1.6.1 Punctuation Adjustment Space, and
+/// Japanese Layout Requirements, section 3.1 Line Composition Rules for
+/// Punctuation Marks.
+pub fn compress_punctuation(glyph: &mut ShapedGlyph, next: &mut ShapedGlyph) {
+    let width = glyph.x_advance;
+    let delta = width / 2.0;
+    if glyph.is_cjk_punctuation()
+        && next.is_cjk_punctuation()
+        && (glyph.shrinkability().1 + next.shrinkability().0) >= delta
+    {
+        let left_delta = glyph.shrinkability().1.min(delta);
+        glyph.shrink_right(left_delta);
+        next.shrink_left(delta - left_delta);
+    }
 }
 
 /// A side you can go toward.
@@ -217,15 +310,26 @@ impl<'a> ShapedText<'a> {
     /// Build the shaped text's frame.
     ///
     /// The `justification` defines how much extra advance width each
-    /// [justifiable glyph](ShapedGlyph::is_justifiable) will get.
+    /// [justifiable glyph](ShapedGlyph::is_justifiable) will get. The
+    /// `expansion_ratio` defines how much of the text's permitted
+    /// [expansion](TextElem::expansion_in) is used to scale the glyphs.
     pub fn build(
         &self,
         vt: &Vt,
         justification_ratio: f64,
         extra_justification: Abs,
+        expansion_ratio: f64,
+        kashida: Abs,
     ) -> Frame {
         let (top, bottom) = self.measure(vt);
-        let size = Size::new(self.width, top + bottom);
+        let expansion = TextElem::expansion_in(self.styles);
+        let scale = |font: &Font| 1.0 + expansion_ratio * expansion.get(font).get();
+        let width = self
+            .glyphs
+            .iter()
+            .map(|g| g.x_advance.at(self.size) * scale(&g.font))
+            .sum();
+        let size = Size::new(width, top + bottom);
 
         let mut offset = Abs::zero();
         let mut frame = Frame::new(size);
@@ -235,9 +339,12 @@ impl<'a> ShapedText<'a> {
         let lang = TextElem::lang_in(self.styles);
         let decos = TextElem::deco_in(self.styles);
         let fill = TextElem::fill_in(self.styles);
+        let vertical = TextElem::vertical_in(self.styles);
 
-        for ((font, y_offset), group) in
-            self.glyphs.as_ref().group_by_key(|g| (g.font.clone(), g.y_offset))
+        for ((font, y_offset, upright, small_cap), group) in self
+            .glyphs
+            .as_ref()
+            .group_by_key(|g| (g.font.clone(), g.y_offset, g.upright, g.small_cap))
         {
             let mut range = group[0].range.clone();
             for glyph in group {
@@ -245,60 +352,116 @@ impl<'a> ShapedText<'a> {
                 range.end = range.end.max(glyph.range.end);
             }
 
-            let pos = Point::new(offset, top + shift - y_offset.at(self.size));
-            let glyphs = group
-                .iter()
-                .map(|glyph| {
-                    let adjustability_left = if justification_ratio < 0.0 {
-                        glyph.shrinkability().0
-                    } else {
-                        glyph.stretchability().0
-                    };
-                    let adjustability_right = if justification_ratio < 0.0 {
-                        glyph.shrinkability().1
-                    } else {
-                        glyph.stretchability().1
-                    };
-
-                    let justification_left = adjustability_left * justification_ratio;
-                    let mut justification_right =
-                        adjustability_right * justification_ratio;
-                    if glyph.is_justifiable() {
-                        justification_right +=
-                            Em::from_length(extra_justification, self.size)
-                    }
+            // Rotated text in vertical lines is centered on the baseline.
+            let center = if vertical && !upright {
+                let metrics = font.metrics();
+                ((metrics.ascender + metrics.descender) / 2.0).at(self.size)
+            } else {
+                Abs::zero()
+            };
 
-                    frame.size_mut().x += justification_left.at(self.size)
-                        + justification_right.at(self.size);
-
-                    Glyph {
-                        id: glyph.glyph_id,
-                        x_advance: glyph.x_advance
-                            + justification_left
-                            + justification_right,
-                        x_offset: glyph.x_offset + justification_left,
-                        range: (glyph.range.start - range.start).saturating_as()
-                            ..(glyph.range.end - range.start).saturating_as(),
-                        span: glyph.span,
+            let scale = scale(&font);
+            let pos = Point::new(offset, top + shift - y_offset.at(self.size) + center);
+            let mut glyphs = Vec::with_capacity(group.len());
+            for glyph in group {
+                // Insert a kashida to the left of the glyph's cluster. It is
+                // formed from as many tatweels as needed, which overlap
+                // slightly to fill the exact amount.
+                if glyph.kashida && kashida > Abs::zero() {
+                    if let Some((id, advance)) = tatweel(&font) {
+                        let amount = Em::from_length(kashida, self.size);
+                        let count = (amount / advance).ceil().max(1.0);
+                        for _ in 0..count as usize {
+                            glyphs.push(Glyph {
+                                id,
+                                x_advance: amount / count,
+                                x_offset: Em::zero(),
+                                range: (glyph.range.start - range.start).saturating_as()
+                                    ..(glyph.range.end - range.start).saturating_as(),
+                                span: glyph.span,
+                            });
+                        }
+                        frame.size_mut().x += kashida;
                     }
-                })
-                .collect();
+                }
+
+                let adjustability_left = if justification_ratio < 0.0 {
+                    glyph.shrinkability().0
+                } else {
+                    glyph.stretchability().0
+                };
+                let adjustability_right = if justification_ratio < 0.0 {
+                    glyph.shrinkability().1
+                } else {
+                    glyph.stretchability().1
+                };
+
+                let justification_left = adjustability_left * justification_ratio;
+                let mut justification_right = adjustability_right * justification_ratio;
+                if glyph.is_justifiable() {
+                    justification_right += Em::from_length(extra_justification, self.size)
+                }
+
+                frame.size_mut().x +=
+                    justification_left.at(self.size) + justification_right.at(self.size);
+
+                glyphs.push(Glyph {
+                    id: glyph.glyph_id,
+                    x_advance: glyph.x_advance * scale
+                        + justification_left
+                        + justification_right,
+                    x_offset: glyph.x_offset * scale + justification_left,
+                    range: (glyph.range.start - range.start).saturating_as()
+                        ..(glyph.range.end - range.start).saturating_as(),
+                    span: glyph.span,
+                });
+            }
+
+            // Synthesized small capitals are set at a smaller size, so their
+            // already scaled advances and offsets must be scaled back up.
+            let factor = if small_cap { smallcap_scale(&font) } else { 1.0 };
+            if small_cap {
+                for glyph in &mut glyphs {
+                    glyph.x_advance /= factor;
+                    glyph.x_offset /= factor;
+                }
+            }
 
+            let (bold, skew) = synthesis(&font, self.variant, self.styles);
             let item = TextItem {
                 font,
-                size: self.size,
+                size: self.size * factor,
                 lang,
                 fill: fill.clone(),
                 text: self.text[range.start - self.base..range.end - self.base].into(),
                 glyphs,
+                scale: Ratio::new(scale),
+                bold: bold / factor,
+                skew,
             };
 
             let layer = frame.layer();
             let width = item.width();
 
+            if upright {
+                let origin = Point::new(offset, top + shift);
+                place_upright(&mut frame, &item, origin, y_offset);
+                offset += width;
+                continue;
+            }
+
             // Apply line decorations.
             for deco in &decos {
-                decorate(&mut frame, deco, &item, shift, pos, width);
+                decorate(
+                    &mut frame,
+                    deco,
+                    &item,
+                    self.styles,
+                    self.size,
+                    shift,
+                    pos,
+                    width,
+                );
             }
 
             frame.insert(layer, pos, FrameItem::Text(item));
@@ -312,7 +475,28 @@ impl<'a> ShapedText<'a> {
     }
 
     /// Measure the top and bottom extent of this text.
-    fn measure(&self, vt: &Vt) -> (Abs, Abs) {
+    pub fn measure(&self, vt: &Vt) -> (Abs, Abs) {
+        // Vertical lines extend equally to both sides of their baseline:
+        // Upright glyphs by half their advance width and rotated text by half
+        // the distance between its font's ascender and descender.
+        if TextElem::vertical_in(self.styles) {
+            let extent = self
+                .glyphs
+                .iter()
+                .map(|g| {
+                    if g.upright {
+                        g.font.advance(g.glyph_id).unwrap_or(Em::one()) / 2.0
+                    } else {
+                        let metrics = g.font.metrics();
+                        (metrics.ascender - metrics.descender) / 2.0
+                    }
+                })
+                .max()
+                .unwrap_or(Em::new(0.5))
+                .at(self.size);
+            return (extent, extent);
+        }
+
         let mut top = Abs::zero();
         let mut bottom = Abs::zero();
 
@@ -334,6 +518,7 @@ impl<'a> ShapedText<'a> {
                     .book()
                     .select(family.as_str(), self.variant)
                     .and_then(|id| world.font(id))
+                    .map(|font| vary(font, self.styles))
                 {
                     expand(&font, None);
                     break;
@@ -359,12 +544,87 @@ impl<'a> ShapedText<'a> {
         self.glyphs.iter().filter(|g| g.is_justifiable()).count()
     }
 
-    /// Whether the last glyph is a CJK character which should not be justified
-    /// on line end.
-    pub fn cjk_justifiable_at_last(&self) -> bool {
+    /// Warn about characters that no font contains and about text that was
+    /// shaped with a last resort fallback font because none of the listed
+    /// families covers it. The latter warning is only emitted once per font.
+    pub fn warn_fallback(&self, vt: &mut Vt) {
+        // With the default font list, fallback is the expected way to find
+        // fonts for other scripts, so it only warrants a warning if the font
+        // list was configured by a set rule. This excludes the font list that
+        // equations set for themselves.
+        let configured = self.styles.property_span(TextElem::func(), "font").is_some();
+        let families: Vec<_> = families(self.styles).collect();
+        let fonts: Vec<_> = families
+            .iter()
+            .filter_map(|family| vt.world.book().select(family.as_str(), self.variant))
+            .filter_map(|id| vt.world.font(id))
+            .collect();
+        let covered =
+            |c: char| fonts.iter().any(|font| font.info().coverage.contains(c as u32));
+        let mut glyphs: Vec<_> = self.glyphs.iter().collect();
+        glyphs.sort_by_key(|g| g.range.start);
+
+        // Group the characters by the text or the font they come from.
+        let mut missing: Vec<(Span, EcoString)> = vec![];
+        let mut fallbacks: Vec<(Span, EcoString, EcoString)> = vec![];
+        for glyph in glyphs {
+            let span = glyph.span.0;
+            if span.is_detached() || glyph.c.is_whitespace() {
+                continue;
+            }
+
+            if glyph.glyph_id == 0 {
+                match missing.iter_mut().find(|(s, _)| *s == span) {
+                    Some((_, chars)) if chars.contains(glyph.c) => {}
+                    Some((_, chars)) => chars.push(glyph.c),
+                    None => missing.push((span, glyph.c.into())),
+                }
+                continue;
+            }
+
+            let family = &glyph.font.info().family;
+            let lowercased = family.to_lowercase();
+            if !configured
+                || families.iter().any(|f| f.as_str() == lowercased)
+                || covered(glyph.c)
+            {
+                continue;
+            }
+
+            match fallbacks.iter_mut().find(|(_, f, _)| f.as_str() == family) {
+                Some((_, _, chars)) if chars.contains(glyph.c) => {}
+                Some((_, _, chars)) => chars.push(glyph.c),
+                None => fallbacks.push((span, family.as_str().into(), glyph.c.into())),
+            }
+        }
+
+        for (span, chars) in missing {
+            vt.tracer
+                .warn(warning!(span, "no font contains the characters {chars:?}"));
+        }
+
+        for (span, family, chars) in fallbacks {
+            vt.tracer.warn_once(warning!(
+                span,
+                "fell back to font {family:?} for the characters {chars:?}"
+            ));
+        }
+    }
+
+    /// The number of positions at which kashidas can be inserted.
+    pub fn kashidas(&self) -> usize {
+        self.glyphs
+            .iter()
+            .filter(|g| g.kashida && tatweel(&g.font).is_some())
+            .count()
+    }
+
+    /// Whether the last glyph is a CJK character or a cluster in a script
+    /// without word separators, which should not be justified on line end.
+    pub fn justifiable_at_last(&self) -> bool {
         self.glyphs
             .last()
-            .map(|g| g.is_cjk_script() || g.is_cjk_punctuation())
+            .map(|g| g.is_cjk_script() || g.is_cjk_punctuation() || g.inter_cluster)
             .unwrap_or(false)
     }
 
@@ -386,6 +646,15 @@ impl<'a> ShapedText<'a> {
             .at(self.size)
     }
 
+    /// How much the text can grow or shrink through font expansion.
+    pub fn expandability(&self) -> Abs {
+        let expansion = TextElem::expansion_in(self.styles);
+        self.glyphs
+            .iter()
+            .map(|g| g.x_advance.at(self.size) * expansion.get(&g.font).get())
+            .sum()
+    }
+
     /// Reshape a range of the shaped text, reusing information from this
     /// shaping process if possible.
     ///
@@ -413,7 +682,7 @@ impl<'a> ShapedText<'a> {
                 glyphs: Cow::Borrowed(glyphs),
             }
         } else {
-            shape(
+            let mut shaped = shape(
                 vt,
                 text_range.start,
                 text,
@@ -422,7 +691,28 @@ impl<'a> ShapedText<'a> {
                 self.dir,
                 self.lang,
                 self.region,
-            )
+            );
+
+            // Keep the spacing to adjacent text items, which was added during
+            // paragraph preparation.
+            if text_range.start == self.base {
+                let amount =
+                    self.glyphs.first().map_or(Em::zero(), |g| g.cjk_latin_spacing.0);
+                if let Some(glyph) = shaped.glyphs.to_mut().first_mut() {
+                    glyph.add_spacing_left(amount - glyph.cjk_latin_spacing.0);
+                }
+            }
+            if text_range.end == self.base + self.text.len() {
+                let amount =
+                    self.glyphs.last().map_or(Em::zero(), |g| g.cjk_latin_spacing.1);
+                if let Some(glyph) = shaped.glyphs.to_mut().last_mut() {
+                    glyph.add_spacing_right(amount - glyph.cjk_latin_spacing.1);
+                }
+            }
+
+            shaped.width =
+                shaped.glyphs.iter().map(|g| g.x_advance).sum::<Em>().at(self.size);
+            shaped
         }
     }
 
@@ -433,7 +723,8 @@ impl<'a> ShapedText<'a> {
             let font = world
                 .book()
                 .select(family.as_str(), self.variant)
-                .and_then(|id| world.font(id))?;
+                .and_then(|id| world.font(id))
+                .map(|font| vary(font, self.styles))?;
             let ttf = font.ttf();
             let glyph_id = ttf.glyph_index('-')?;
             let x_advance = font.to_em(ttf.glyph_hor_advance(glyph_id)?);
@@ -453,6 +744,11 @@ impl<'a> ShapedText<'a> {
                 range,
                 safe_to_break: true,
                 c: '-',
+                cjk_latin_spacing: (Em::zero(), Em::zero()),
+                kashida: false,
+                inter_cluster: false,
+                upright: false,
+                small_cap: false,
                 span: (Span::detached(), 0),
             });
             Some(())
@@ -518,6 +814,41 @@ impl<'a> ShapedText<'a> {
     }
 }
 
+/// Place the glyphs of an upright run in a vertical line.
+///
+/// Vertical lines are laid out horizontally and rotated by a quarter turn
+/// clockwise afterwards. Thus, each upright glyph is put into its own group
+/// that is rotated the other way. Within the group, the glyph is positioned
+/// relative to the center of the line at its pen position.
+fn place_upright(frame: &mut Frame, item: &TextItem, origin: Point, y_offset: Em) {
+    let mut advance = Abs::zero();
+    for glyph in &item.glyphs {
+        let range = glyph.range();
+        let single = TextItem {
+            text: item.text[range.clone()].into(),
+            glyphs: vec![Glyph {
+                x_advance: item.font.advance(glyph.id).unwrap_or(glyph.x_advance),
+                x_offset: Em::zero(),
+                range: 0..range.len().saturating_as(),
+                ..glyph.clone()
+            }],
+            scale: Ratio::one(),
+            ..item.clone()
+        };
+
+        let mut sub = Frame::new(Size::zero());
+        let pos = Point::new(y_offset.at(item.size), glyph.x_offset.at(item.size));
+        sub.push(pos, FrameItem::Text(single));
+
+        let mut group = GroupItem::new(sub);
+        group.transform = Transform::translate(origin.x + advance, origin.y)
+            .pre_concat(Transform::rotate(Angle::deg(-90.0)));
+        frame.push(Point::zero(), FrameItem::Group(group));
+
+        advance += glyph.x_advance.at(item.size);
+    }
+}
+
 impl Debug for ShapedText<'_> {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         self.text.fmt(f)
@@ -536,6 +867,7 @@ struct ShapingContext<'a, 'v> {
     tags: Vec<rustybuzz::Feature>,
     fallback: bool,
     dir: Dir,
+    upright: bool,
 }
 
 /// Shape text into [`ShapedText`].
@@ -562,14 +894,36 @@ pub fn shape<'a>(
         tags: tags(styles),
         fallback: TextElem::fallback_in(styles),
         dir,
+        upright: false,
     };
 
-    if !text.is_empty() {
+    if TextElem::vertical_in(styles) && dir.is_positive() {
+        // In vertical text, shape runs of upright characters top-to-bottom
+        // and the remaining ones as usual, to be rotated with the line.
+        let mut start = 0;
+        while start < text.len() {
+            let upright = text[start..].chars().next().map_or(false, is_upright);
+            let end = text[start..]
+                .char_indices()
+                .skip(1)
+                .find(|&(_, c)| {
+                    c.script() != Script::Inherited && is_upright(c) != upright
+                })
+                .map_or(text.len(), |(i, _)| start + i);
+            ctx.upright = upright;
+            shape_segment(&mut ctx, base + start, &text[start..end], families(styles));
+            start = end;
+        }
+        ctx.upright = false;
+    } else if !text.is_empty() {
         shape_segment(&mut ctx, base, text, families(styles));
     }
 
     track_and_space(&mut ctx);
     calculate_adjustability(&mut ctx, lang, region);
+    space_cjk_latin(&mut ctx);
+    mark_kashidas(&mut ctx);
+    mark_inter_cluster(&mut ctx);
 
     #[cfg(debug_assertions)]
     assert_all_glyphs_in_range(&ctx.glyphs, text, base..(base + text.len()));
@@ -606,9 +960,16 @@ fn shape_segment(
     let world = ctx.vt.world;
     let book = world.book();
     let mut selection = families.find_map(|family| {
+        let covers = family.covers().cloned();
+        if covers.as_ref().map_or(false, |covers| !covers.applies_to(text)) {
+            return None;
+        }
+
         book.select(family.as_str(), ctx.variant)
             .and_then(|id| world.font(id))
+            .map(|font| vary(font, ctx.styles))
             .filter(|font| !ctx.used.contains(font))
+            .map(|font| (font, covers))
     });
 
     // Do font fallback if the families are exhausted and fallback is enabled.
@@ -617,11 +978,13 @@ fn shape_segment(
         selection = book
             .select_fallback(first, ctx.variant, text)
             .and_then(|id| world.font(id))
-            .filter(|font| !ctx.used.contains(font));
+            .map(|font| vary(font, ctx.styles))
+            .filter(|font| !ctx.used.contains(font))
+            .map(|font| (font, None));
     }
 
     // Extract the font id or shape notdef glyphs if we couldn't find any font.
-    let Some(font) = selection else {
+    let Some((font, covers)) = selection else {
         if let Some(font) = ctx.used.first().cloned() {
             shape_tofus(ctx, base, text, font);
         }
@@ -630,27 +993,32 @@ fn shape_segment(
 
     ctx.used.push(font.clone());
 
-    // Fill the buffer with our text.
-    let mut buffer = UnicodeBuffer::new();
-    buffer.push_str(text);
-    buffer.set_language(language(ctx.styles));
-    if let Some(script) = TextElem::script_in(ctx.styles).as_custom().and_then(|script| {
-        rustybuzz::Script::from_iso15924_tag(Tag::from_bytes(script.as_bytes()))
-    }) {
-        buffer.set_script(script)
-    }
-    buffer.set_direction(match ctx.dir {
-        Dir::LTR => rustybuzz::Direction::LeftToRight,
-        Dir::RTL => rustybuzz::Direction::RightToLeft,
-        _ => unimplemented!("vertical text layout"),
-    });
-
-    // Shape!
-    let buffer = rustybuzz::shape(font.rusty(), &ctx.tags, buffer);
-    let infos = buffer.glyph_infos();
-    let pos = buffer.glyph_positions();
+    let (bold, _) = synthesis(&font, ctx.variant, ctx.styles);
+    let smallcaps = synthesize_smallcaps(&font, ctx.styles);
+
+    // Shape the text, reusing the results for identical runs.
+    let options = RunOptions {
+        language: language(ctx.styles),
+        script: TextElem::script_in(ctx.styles)
+            .as_custom()
+            .map(|script| Tag::from_bytes(script.as_bytes())),
+        dir: ctx.dir,
+        upright: ctx.upright,
+        smallcaps,
+        features: ctx.tags.iter().map(|f| (f.tag, f.value)).collect(),
+    };
+    let infos = shape_run(&font, text, &options);
     let ltr = ctx.dir.is_positive();
 
+    // Glyphs for characters that the family doesn't cover are treated like
+    // missing ones.
+    let missing = |info: &RunGlyph| {
+        info.glyph_id == 0
+            || covers.as_ref().map_or(false, |covers| {
+                !covers.contains(text[info.cluster as usize..].chars().next().unwrap())
+            })
+    };
+
     // Collect the shaped glyphs, doing fallback and shaping parts again with
     // the next font if necessary.
     let mut i = 0;
@@ -659,7 +1027,7 @@ fn shape_segment(
         let cluster = info.cluster as usize;
 
         // Add the glyph to the shaped output.
-        if info.glyph_id != 0 {
+        if !missing(info) {
             // Determine the text range of the glyph.
             let start = base + cluster;
             let end = base
@@ -667,23 +1035,56 @@ fn shape_segment(
                     .and_then(|last| infos.get(last))
                     .map_or(text.len(), |info| info.cluster as usize);
 
+            let c = text[cluster..].chars().next().unwrap();
+
+            // Upright glyphs advance downwards. Their positions are
+            // relative to their horizontal origin, so that the vertical
+            // offset runs along the line.
+            let (x_advance, mut x_offset, mut y_offset) = if ctx.upright {
+                (-info.y_advance, -info.y_offset, info.x_offset)
+            } else {
+                (info.x_advance, info.x_offset, info.y_offset)
+            };
+
+            // Without a `vert` substitution, commas and full stops keep their
+            // horizontal forms in the lower left of the em box, but vertical
+            // text expects them in the upper right.
+            if ctx.upright
+                && matches!(c, '、' | '。' | '，' | '．' | '､' | '｡')
+                && font.ttf().glyph_index(c).map(|id| id.0 as u32) == Some(info.glyph_id)
+            {
+                let id = ttf_parser::GlyphId(info.glyph_id as u16);
+                let half = font.ttf().glyph_hor_advance(id).unwrap_or(0) as i32 / 2;
+                x_offset -= half;
+                y_offset += half;
+            }
+
+            // Synthesized small capitals are scaled down and synthesized
+            // bold widens the glyph by the stroke around its outline.
+            let small_cap = smallcaps && c.is_lowercase();
+            let factor = if small_cap { smallcap_scale(&font) } else { 1.0 };
+
             ctx.glyphs.push(ShapedGlyph {
                 font: font.clone(),
                 glyph_id: info.glyph_id as u16,
-                // TODO: Don't ignore y_advance.
-                x_advance: font.to_em(pos[i].x_advance),
-                x_offset: font.to_em(pos[i].x_offset),
-                y_offset: font.to_em(pos[i].y_offset),
+                x_advance: font.to_em(x_advance) * factor + bold,
+                x_offset: font.to_em(x_offset) * factor + bold / 2.0,
+                y_offset: font.to_em(y_offset) * factor,
                 adjustability: Adjustability::default(),
                 range: start..end,
-                safe_to_break: !info.unsafe_to_break(),
-                c: text[cluster..].chars().next().unwrap(),
+                safe_to_break: !info.unsafe_to_break,
+                c,
+                cjk_latin_spacing: (Em::zero(), Em::zero()),
+                kashida: false,
+                inter_cluster: false,
+                upright: ctx.upright,
+                small_cap,
                 span: ctx.spans.span_at(start),
             });
         } else {
             // First, search for the end of the tofu sequence.
             let k = i;
-            while infos.get(i + 1).map_or(false, |info| info.glyph_id == 0) {
+            while infos.get(i + 1).map_or(false, missing) {
                 i += 1;
             }
 
@@ -727,6 +1128,152 @@ fn shape_segment(
     ctx.used.pop();
 }
 
+/// What a text run is shaped with, apart from its font and text.
+#[derive(Debug, Clone, PartialEq, Hash)]
+struct RunOptions {
+    /// The BCP 47 language tag.
+    language: EcoString,
+    /// The configured OpenType script. Otherwise, the shaper guesses it.
+    script: Option<Tag>,
+    /// The direction of the text.
+    dir: Dir,
+    /// Whether the run is set upright in vertical text.
+    upright: bool,
+    /// Whether lowercase letters are shaped as capitals for synthesized small
+    /// capitals.
+    smallcaps: bool,
+    /// The OpenType features as their tag and value. They apply to the whole
+    /// run.
+    features: Vec<(Tag, u32)>,
+}
+
+/// A glyph in a shaped run, with positions in font units.
+#[derive(Debug, Clone)]
+struct RunGlyph {
+    glyph_id: u32,
+    cluster: u32,
+    unsafe_to_break: bool,
+    x_advance: i32,
+    y_advance: i32,
+    x_offset: i32,
+    y_offset: i32,
+}
+
+/// Shape a run of text with a single font.
+///
+/// The same runs recur across paragraphs and the same paragraphs across
+/// recompilations, so the results are memoized. Shaping happens in font
+/// units, so the text size doesn't need to be part of the key. Unless
+/// configured, the script is guessed by the shaper, which is thus also
+/// memoized. Like all memoized results, unused runs are evicted from the
+/// cache after a few compilations.
+#[comemo::memoize]
+fn shape_run(font: &Font, text: &str, options: &RunOptions) -> Arc<Vec<RunGlyph>> {
+    // Fill the buffer with our text. For synthesized small capitals, the
+    // lowercase letters are shaped as capitals.
+    let mut buffer = UnicodeBuffer::new();
+    if options.smallcaps {
+        for (i, c) in text.char_indices() {
+            if c.is_lowercase() {
+                c.to_uppercase().for_each(|upper| buffer.add(upper, i as u32));
+            } else {
+                buffer.add(c, i as u32);
+            }
+        }
+    } else {
+        buffer.push_str(text);
+    }
+
+    buffer.set_language(rustybuzz::Language::from_str(&options.language).unwrap());
+    if let Some(script) = options.script.and_then(rustybuzz::Script::from_iso15924_tag) {
+        buffer.set_script(script)
+    }
+
+    // Shaping top-to-bottom applies the font's vertical metrics and the
+    // `vert` feature for vertical glyph forms.
+    buffer.set_direction(match options.dir {
+        _ if options.upright => rustybuzz::Direction::TopToBottom,
+        Dir::RTL => rustybuzz::Direction::RightToLeft,
+        // Sideways text in vertical lines is shaped like horizontal text.
+        _ => rustybuzz::Direction::LeftToRight,
+    });
+
+    let features: Vec<Feature> = options
+        .features
+        .iter()
+        .map(|&(tag, value)| Feature::new(tag, value, ..))
+        .collect();
+
+    // Shape!
+    let buffer = rustybuzz::shape(font.rusty(), &features, buffer);
+    let glyphs = buffer
+        .glyph_infos()
+        .iter()
+        .zip(buffer.glyph_positions())
+        .map(|(info, pos)| RunGlyph {
+            glyph_id: info.glyph_id,
+            cluster: info.cluster,
+            unsafe_to_break: info.unsafe_to_break(),
+            x_advance: pos.x_advance,
+            y_advance: pos.y_advance,
+            x_offset: pos.x_offset,
+            y_offset: pos.y_offset,
+        })
+        .collect();
+
+    Arc::new(glyphs)
+}
+
+/// The stroke width for synthesized bold and the skew for synthesized italics
+/// of text in the given font.
+pub(crate) fn synthesis(
+    font: &Font,
+    variant: FontVariant,
+    styles: StyleChain,
+) -> (Em, Ratio) {
+    let synthesis = TextElem::synthesis_in(styles);
+    let actual = font.info().variant;
+
+    let bold = if synthesis.bold()
+        && variant.weight >= FontWeight::SEMIBOLD
+        && actual.weight <= FontWeight::MEDIUM
+    {
+        Em::new(1.0 / 24.0)
+    } else {
+        Em::zero()
+    };
+
+    // Slant by 12 degrees.
+    let skew = if synthesis.italic()
+        && variant.style != FontStyle::Normal
+        && actual.style == FontStyle::Normal
+    {
+        Ratio::new(0.2126)
+    } else {
+        Ratio::zero()
+    };
+
+    (bold, skew)
+}
+
+/// Whether to synthesize small capitals for text in the given font.
+fn synthesize_smallcaps(font: &Font, styles: StyleChain) -> bool {
+    TextElem::smallcaps_in(styles)
+        && TextElem::synthesis_in(styles).smallcaps()
+        && !font.ttf().tables().gsub.map_or(false, |gsub| {
+            gsub.features
+                .into_iter()
+                .any(|feature| feature.tag == Tag::from_bytes(b"smcp"))
+        })
+}
+
+/// The size of synthesized small capitals relative to the font size, such
+/// that they are about as high as lowercase letters.
+fn smallcap_scale(font: &Font) -> f64 {
+    let metrics = font.metrics();
+    (metrics.x_height / metrics.cap_height).clamp(0.6, 0.9)
+}
+
 /// Shape the text with tofus from the given font.
 fn shape_tofus(ctx: &mut ShapingContext, base: usize, text: &str, font: Font) {
     let x_advance = font.advance(0).unwrap_or_default();
@@ -743,6 +1290,11 @@ fn shape_tofus(ctx: &mut ShapingContext, base: usize, text: &str, font: Font) {
             range: start..end,
             safe_to_break: true,
             c,
+            cjk_latin_spacing: (Em::zero(), Em::zero()),
+            kashida: false,
+            inter_cluster: false,
+            upright: ctx.upright,
+            small_cap: false,
             span: ctx.spans.span_at(start),
         });
     };
@@ -795,30 +1347,228 @@ fn calculate_adjustability(ctx: &mut ShapingContext, lang: Lang, region: Option<
         glyph.adjustability = glyph.base_adjustability(gb_style);
     }
 
+    // Only GB style needs further adjustment.
+    if !gb_style || !TextElem::compress_punctuation_in(ctx.styles) {
+        return;
+    }
+
     let mut glyphs = ctx.glyphs.iter_mut().peekable();
     while let Some(glyph) = glyphs.next() {
-        // Only GB style needs further adjustment.
-        if glyph.is_cjk_punctuation() && !gb_style {
+        let Some(next) = glyphs.peek_mut() else { continue };
+        compress_punctuation(glyph, next);
+    }
+}
+
+/// The glyph id and advance of a font's tatweel, which is repeated to form a
+/// kashida.
+fn tatweel(font: &Font) -> Option<(u16, Em)> {
+    let id = font.ttf().glyph_index('\u{640}')?.0;
+    let advance = font.advance(id)?;
+    (advance > Em::zero()).then_some((id, advance))
+}
+
+/// Mark the position in each Arabic word where a kashida may be inserted.
+///
+/// Kashidas can only be inserted between two joined letters. Among these
+/// positions, those after the letters of the seen family and before certain
+/// final letters are preferred. If there are multiple equally good positions,
+/// the last one is chosen.
+fn mark_kashidas(ctx: &mut ShapingContext) {
+    if ctx.dir != Dir::RTL {
+        return;
+    }
+
+    if let Smart::Custom(script) = TextElem::script_in(ctx.styles) {
+        if script.as_str() != "arab" {
+            return;
+        }
+    }
+
+    // The clusters in logical order as the index of their leftmost glyph and
+    // their first char. Transparent marks don't affect joining.
+    let glyphs = &mut ctx.glyphs;
+    let clusters: Vec<(usize, char)> = (0..glyphs.len())
+        .rev()
+        .filter(|&i| i == 0 || glyphs[i - 1].range != glyphs[i].range)
+        .map(|i| (i, glyphs[i].c))
+        .filter(|&(_, c)| !is_arabic_transparent(c))
+        .collect();
+
+    let is_letter = |c: char| is_dual_joining(c) || is_right_joining(c);
+    let mut best: Option<(usize, u8)> = None;
+    for (k, &(i, a)) in clusters.iter().enumerate() {
+        // Anything that isn't an Arabic letter ends the word.
+        if !is_letter(a) {
+            if let Some((i, _)) = best.take() {
+                glyphs[i].kashida = true;
+            }
             continue;
         }
 
-        // Now we apply consecutive punctuation adjustment, specified in Chinese Layout
-        // Requirements, section 3.1.6.1 Punctuation Adjustment Space, and Japanese Layout
-        // Requirements, section 3.1 Line Composition Rules for Punctuation Marks
-        let Some(next) = glyphs.peek_mut() else { continue };
-        let width = glyph.x_advance;
-        let delta = width / 2.0;
-        if glyph.is_cjk_punctuation()
-            && next.is_cjk_punctuation()
-            && (glyph.shrinkability().1 + next.shrinkability().0) >= delta
-        {
-            let left_delta = glyph.shrinkability().1.min(delta);
-            glyph.shrink_right(left_delta);
-            next.shrink_left(delta - left_delta);
+        let Some(&(_, b)) = clusters.get(k + 1) else { continue };
+        if !is_dual_joining(a) || !is_letter(b) {
+            continue;
+        }
+
+        let after = clusters.get(k + 2).map(|&(_, c)| c);
+        let is_final = is_right_joining(b) || !after.map_or(false, is_letter);
+        let priority = kashida_priority(a, b, is_final);
+        if priority > 0 && best.map_or(true, |(_, p)| priority >= p) {
+            best = Some((i, priority));
+        }
+    }
+
+    if let Some((i, _)) = best {
+        glyphs[i].kashida = true;
+    }
+}
+
+/// How suitable the position between the joined Arabic letters `a` and `b`
+/// is for a kashida. Zero means that it is not suitable at all.
+fn kashida_priority(a: char, b: char, is_final: bool) -> u8 {
+    match (a, b) {
+        // Lam and alef form a ligature.
+        ('\u{644}', '\u{622}' | '\u{623}' | '\u{625}' | '\u{627}') => 0,
+        // After seen, sheen, sad and dad.
+        ('\u{633}'..='\u{636}', _) => 5,
+        // Before final teh marbuta, heh and dal.
+        (_, '\u{629}' | '\u{647}' | '\u{62F}' | '\u{630}') if is_final => 4,
+        // Before final alef, tah, zah, lam, kaf and gaf.
+        (
+            _,
+            '\u{627}' | '\u{637}' | '\u{638}' | '\u{644}' | '\u{643}' | '\u{6A9}'
+            | '\u{6AF}',
+        ) if is_final => 3,
+        // Before final reh, zain, waw and yeh.
+        (_, '\u{631}' | '\u{632}' | '\u{648}' | '\u{649}' | '\u{64A}') if is_final => 2,
+        _ => 1,
+    }
+}
+
+/// Whether an Arabic letter joins to both of its neighbours.
+fn is_dual_joining(c: char) -> bool {
+    matches!(
+        c,
+        '\u{620}'
+            | '\u{626}'
+            | '\u{628}'
+            | '\u{62A}'..='\u{62E}'
+            | '\u{633}'..='\u{63F}'
+            | '\u{641}'..='\u{647}'
+            | '\u{649}'
+            | '\u{64A}'
+            | '\u{66E}'
+            | '\u{66F}'
+            | '\u{678}'..='\u{687}'
+            | '\u{69A}'..='\u{6BF}'
+            | '\u{6C1}'
+            | '\u{6C2}'
+            | '\u{6CC}'
+            | '\u{6CE}'
+            | '\u{6D0}'
+            | '\u{6D1}'
+            | '\u{6FA}'..='\u{6FC}'
+            | '\u{6FF}'
+    )
+}
+
+/// Whether an Arabic letter only joins to the preceding letter.
+fn is_right_joining(c: char) -> bool {
+    matches!(
+        c,
+        '\u{622}'..='\u{625}'
+            | '\u{627}'
+            | '\u{629}'
+            | '\u{62F}'..='\u{632}'
+            | '\u{648}'
+            | '\u{671}'..='\u{673}'
+            | '\u{675}'..='\u{677}'
+            | '\u{688}'..='\u{699}'
+            | '\u{6C0}'
+            | '\u{6C3}'..='\u{6CB}'
+            | '\u{6CD}'
+            | '\u{6CF}'
+            | '\u{6D2}'
+            | '\u{6D3}'
+            | '\u{6EE}'
+            | '\u{6EF}'
+    )
+}
+
+/// Whether a char is an Arabic mark that doesn't affect joining.
+fn is_arabic_transparent(c: char) -> bool {
+    matches!(
+        c,
+        '\u{610}'..='\u{61A}'
+            | '\u{64B}'..='\u{65F}'
+            | '\u{670}'
+            | '\u{6D6}'..='\u{6DC}'
+            | '\u{6DF}'..='\u{6E4}'
+            | '\u{6E7}'
+            | '\u{6E8}'
+            | '\u{6EA}'..='\u{6ED}'
+    )
+}
+
+/// How much space may be inserted between two clusters of a justified line
+/// before extra space is distributed between all justifiable characters.
+pub const INTER_CLUSTER_STRETCH: Em = Em::new(0.05);
+
+/// Mark the clusters after which extra space may be inserted to justify text
+/// in Thai, Lao, Khmer and Burmese. These scripts don't separate words with
+/// spaces, so lines are justified between clusters instead.
+fn mark_inter_cluster(ctx: &mut ShapingContext) {
+    if ctx.dir != Dir::LTR {
+        return;
+    }
+
+    let glyphs = &mut ctx.glyphs;
+    for i in 1..glyphs.len() {
+        let (prev, next) = (&glyphs[i - 1], &glyphs[i]);
+        let justifiable = prev.range != next.range
+            && is_without_word_separators(prev.c)
+            && is_without_word_separators(next.c);
+        glyphs[i - 1].inter_cluster = justifiable;
+        if justifiable {
+            glyphs[i - 1].adjustability.stretchability.1 += INTER_CLUSTER_STRETCH;
         }
     }
 }
 
+/// Whether a char belongs to a script that doesn't separate words with spaces.
+fn is_without_word_separators(c: char) -> bool {
+    matches!(c.script(), Script::Thai | Script::Lao | Script::Khmer | Script::Myanmar)
+}
+
+/// Add spacing between CJK characters and Latin text in the same run.
+fn space_cjk_latin(ctx: &mut ShapingContext) {
+    if !ctx.dir.is_positive() || !TextElem::cjk_latin_spacing_in(ctx.styles) {
+        return;
+    }
+
+    let mut glyphs = ctx.glyphs.iter_mut().peekable();
+    while let Some(glyph) = glyphs.next() {
+        let Some(next) = glyphs.peek_mut() else { continue };
+        add_cjk_latin_spacing(glyph, next);
+    }
+}
+
+/// Whether a character stands upright in vertical text.
+///
+/// This approximates the Unicode vertical orientation property for the
+/// scripts and symbols of East Asian text.
+fn is_upright(c: char) -> bool {
+    use Script::*;
+    matches!(c.script(), Han | Hiragana | Katakana | Hangul | Bopomofo | Yi)
+        || matches!(c,
+            '\u{3000}'..='\u{303F}' // CJK Symbols and Punctuation
+            | '\u{3200}'..='\u{33FF}' // Enclosed CJK and CJK Compatibility
+            | '\u{FE30}'..='\u{FE4F}' // CJK Compatibility Forms
+            | '\u{FF01}'..='\u{FF60}' // Fullwidth Forms
+            | '\u{FFE0}'..='\u{FFE6}'
+        )
+}
+
 /// Difference between non-breaking and normal space.
 fn nbsp_delta(font: &Font) -> Option<Em> {
     let space = font.ttf().glyph_index(' ')?.0;
@@ -826,6 +1576,47 @@ fn nbsp_delta(font: &Font) -> Option<Em> {
     Some(font.advance(nbsp)? - font.advance(space)?)
 }
 
+/// Instantiate a variable font with the configured axis values.
+///
+/// Unless set explicitly, the `wght` and `wdth` axes follow the text's weight
+/// and stretch. These are only set where they differ from the font's current
+/// values, so that text which matches the font as selected doesn't create a
+/// new instance. The `opsz` axis is never set automatically since PDF export
+/// draws the glyphs of instances as paths.
+pub(crate) fn vary(font: Font, styles: StyleChain) -> Font {
+    if !font.is_variable() {
+        return font;
+    }
+
+    let variant = variant(styles);
+    let stretch = variant.stretch.to_ratio().get() * 100.0;
+    let mut variations: Vec<_> =
+        [(*b"wght", variant.weight.to_number() as f32), (*b"wdth", stretch as f32)]
+            .into_iter()
+            .filter(|&(tag, value)| font.variation(tag).map_or(false, |v| v != value))
+            .map(|(tag, value)| FontVariation::new(tag, value))
+            .collect();
+
+    variations.extend(
+        TextElem::variations_in(styles)
+            .0
+            .into_iter()
+            .map(|(tag, value)| FontVariation::new(tag, value.0 as f32)),
+    );
+
+    if variations.is_empty() {
+        return font;
+    }
+
+    instantiate(font, variations)
+}
+
+/// Instantiate a font with the given axis values.
+#[comemo::memoize]
+fn instantiate(font: Font, variations: Vec<FontVariation>) -> Font {
+    font.with_variations(&variations)
+}
+
 /// Resolve the font variant.
 pub fn variant(styles: StyleChain) -> FontVariant {
     let mut variant = FontVariant::new(
@@ -935,13 +1726,13 @@ pub fn tags(styles: StyleChain) -> Vec<Feature> {
 
 /// Process the language and and region of a style chain into a
 /// rustybuzz-compatible BCP 47 language.
-fn language(styles: StyleChain) -> rustybuzz::Language {
+fn language(styles: StyleChain) -> EcoString {
     let mut bcp: EcoString = TextElem::lang_in(styles).as_str().into();
     if let Some(region) = TextElem::region_in(styles) {
         bcp.push('-');
         bcp.push_str(region.as_str());
     }
-    rustybuzz::Language::from_str(&bcp).unwrap()
+    bcp
 }
 
 /// Returns true if all glyphs in `glyphs` have ranges within the range `range`.
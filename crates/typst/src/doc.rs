@@ -11,8 +11,9 @@ use ecow::EcoString;
 use crate::eval::{cast, dict, Dict, Value};
 use crate::font::Font;
 use crate::geom::{
-    self, rounded_rect, Abs, Align, Axes, Color, Corners, Dir, Em, Geometry, Length,
-    Numeric, Paint, Point, Rel, RgbaColor, Shape, Sides, Size, Stroke, Transform,
+    self, rounded_rect, Abs, Align, Angle, Axes, Color, Corners, Dir, Em, Geometry,
+    Length, Numeric, Paint, Point, Ratio, Rel, RgbaColor, Shape, Sides, Size, Stroke,
+    Transform,
 };
 use crate::image::Image;
 use crate::model::{Content, Location, MetaElem, StyleChain};
@@ -475,6 +476,15 @@ pub struct TextItem {
     pub text: EcoString,
     /// The glyphs.
     pub glyphs: Vec<Glyph>,
+    /// The horizontal scale of the glyph outlines, used for font expansion.
+    /// The glyphs' advances and offsets already include this scale.
+    pub scale: Ratio,
+    /// The width of a stroke in the glyphs' color around their outlines,
+    /// used for synthesized bold.
+    pub bold: Em,
+    /// How far the glyph outlines are slanted to the right per unit of
+    /// height, used for synthesized italics.
+    pub skew: Ratio,
 }
 
 impl TextItem {
@@ -668,6 +678,12 @@ pub enum Meta {
     Elem(Content),
     /// The numbering of the current page.
     PageNumbering(Value),
+    /// The clockwise rotation with which the current page is displayed. The
+    /// page's frame is in display orientation.
+    PageRotation(Angle),
+    /// An element that marks a position for a later layout pass. Unlike
+    /// `Elem`, it is not locatable and thus invisible to introspection.
+    Marker(Content),
     /// Indicates that content should be hidden. This variant doesn't appear
     /// in the final frames as it is removed alongside the content that should
     /// be hidden.
@@ -684,6 +700,8 @@ impl Debug for Meta {
             Self::Link(dest) => write!(f, "Link({dest:?})"),
             Self::Elem(content) => write!(f, "Elem({:?})", content.func()),
             Self::PageNumbering(value) => write!(f, "PageNumbering({value:?})"),
+            Self::PageRotation(angle) => write!(f, "PageRotation({angle:?})"),
+            Self::Marker(content) => write!(f, "Marker({:?})", content.func()),
             Self::Hide => f.pad("Hide"),
         }
     }
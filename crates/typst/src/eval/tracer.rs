@@ -67,4 +67,13 @@ impl Tracer {
             self.warnings.push(warning);
         }
     }
+
+    /// Add a warning unless one with the same message was already added,
+    /// no matter at which span.
+    pub fn warn_once(&mut self, warning: SourceDiagnostic) {
+        let hash = hash128(&warning.message);
+        if self.warnings_set.insert(hash) {
+            self.warnings.push(warning);
+        }
+    }
 }
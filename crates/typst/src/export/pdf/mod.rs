@@ -18,7 +18,7 @@ use xmp_writer::{LangId, RenditionClass, XmpWriter};
 use self::page::Page;
 use crate::doc::{Document, Lang};
 use crate::font::Font;
-use crate::geom::{Abs, Dir, Em};
+use crate::geom::{Abs, Dir, Em, Transform};
 use crate::image::Image;
 use crate::model::Introspector;
 
@@ -49,7 +49,7 @@ pub struct PdfContext<'a> {
     introspector: Introspector,
     writer: PdfWriter,
     pages: Vec<Page>,
-    page_heights: Vec<f32>,
+    page_transforms: Vec<Transform>,
     alloc: Ref,
     page_tree_ref: Ref,
     font_refs: Vec<Ref>,
@@ -78,7 +78,7 @@ impl<'a> PdfContext<'a> {
             introspector: Introspector::new(&document.pages),
             writer: PdfWriter::new(),
             pages: vec![],
-            page_heights: vec![],
+            page_transforms: vec![],
             alloc,
             page_tree_ref,
             page_refs: vec![],
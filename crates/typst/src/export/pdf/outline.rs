@@ -2,8 +2,9 @@ use std::num::NonZeroUsize;
 
 use pdf_writer::{Finish, Ref, TextStr};
 
+use super::page::destination;
 use super::{AbsExt, PdfContext, RefExt};
-use crate::geom::{Abs, Smart};
+use crate::geom::Smart;
 use crate::model::Content;
 
 /// Construct the outline for the document.
@@ -163,14 +164,13 @@ fn write_outline_item(
 
     let loc = node.element.location().unwrap();
     let pos = ctx.introspector.position(loc);
-    let index = pos.page.get() - 1;
-    if let Some(&height) = ctx.page_heights.get(index) {
-        let y = (pos.point.y - Abs::pt(10.0)).max(Abs::zero());
-        outline.dest().page(ctx.page_refs[index]).xyz(
-            pos.point.x.to_f32(),
-            height - y.to_f32(),
-            None,
-        );
+    if let Some((page_ref, point)) =
+        destination(&ctx.page_refs, &ctx.page_transforms, pos)
+    {
+        outline
+            .dest()
+            .page(page_ref)
+            .xyz(point.x.to_f32(), point.y.to_f32(), None);
     }
 
     outline.finish();
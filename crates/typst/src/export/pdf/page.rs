@@ -1,19 +1,22 @@
 use ecow::eco_format;
 use pdf_writer::types::{
     ActionType, AnnotationType, ColorSpaceOperand, LineCapStyle, LineJoinStyle,
+    TextRenderingMode,
 };
 use pdf_writer::writers::ColorSpace;
 use pdf_writer::{Content, Filter, Finish, Name, Rect, Ref, Str};
+use ttf_parser::{GlyphId, OutlineBuilder};
 
 use super::external_graphics_state::ExternalGraphicsState;
 use super::{deflate, AbsExt, EmExt, PdfContext, RefExt, D65_GRAY, SRGB};
-use crate::doc::{Destination, Frame, FrameItem, GroupItem, Meta, TextItem};
+use crate::doc::{Destination, Frame, FrameItem, GroupItem, Meta, Position, TextItem};
+use crate::export::svg::convert_svg_glyph;
 use crate::font::Font;
 use crate::geom::{
     self, Abs, Color, Em, Geometry, LineCap, LineJoin, Numeric, Paint, Point, Ratio,
     Shape, Size, Stroke, Transform,
 };
-use crate::image::Image;
+use crate::image::{Image, ImageFormat, VectorFormat};
 
 /// Construct page objects.
 #[tracing::instrument(skip_all)]
@@ -28,7 +31,12 @@ pub fn construct_pages(ctx: &mut PdfContext, frames: &[Frame]) {
 pub fn construct_page(ctx: &mut PdfContext, frame: &Frame) {
     let page_ref = ctx.alloc.bump();
     ctx.page_refs.push(page_ref);
-    ctx.page_heights.push(frame.height().to_f32());
+
+    // A rotated page is stored with its physical size and rotated for
+    // display by the viewer, so its content must be rotated the other way.
+    let rotation = page_rotation(frame);
+    let (size, ts) = page_transform(frame, rotation);
+    ctx.page_transforms.push(ts);
 
     let mut ctx = PageContext {
         parent: ctx,
@@ -41,24 +49,15 @@ pub fn construct_page(ctx: &mut PdfContext, frame: &Frame) {
         links: vec![],
     };
 
-    let size = frame.size();
-
-    // Make the coordinate system start at the top-left.
     ctx.bottom = size.y.to_f32();
-    ctx.transform(Transform {
-        sx: Ratio::one(),
-        ky: Ratio::zero(),
-        kx: Ratio::zero(),
-        sy: Ratio::new(-1.0),
-        tx: Abs::zero(),
-        ty: size.y,
-    });
+    ctx.transform(ts);
 
     // Encode the page into the content stream.
     write_frame(&mut ctx, frame);
 
     let page = Page {
         size,
+        rotation,
         content: ctx.content,
         id: ctx.page_ref,
         uses_opacities: ctx.uses_opacities,
@@ -68,6 +67,73 @@ pub fn construct_page(ctx: &mut PdfContext, frame: &Frame) {
     ctx.parent.pages.push(page);
 }
 
+/// The physical size of a page and the transform from its frame's coordinates
+/// into the PDF coordinate system.
+fn page_transform(frame: &Frame, rotation: i32) -> (Size, Transform) {
+    let (w, h) = (frame.width(), frame.height());
+    match rotation {
+        90 => (
+            Size::new(h, w),
+            Transform {
+                sx: Ratio::zero(),
+                ky: Ratio::one(),
+                kx: Ratio::one(),
+                sy: Ratio::zero(),
+                tx: Abs::zero(),
+                ty: Abs::zero(),
+            },
+        ),
+        180 => (
+            Size::new(w, h),
+            Transform {
+                sx: Ratio::new(-1.0),
+                ky: Ratio::zero(),
+                kx: Ratio::zero(),
+                sy: Ratio::one(),
+                tx: w,
+                ty: Abs::zero(),
+            },
+        ),
+        270 => (
+            Size::new(h, w),
+            Transform {
+                sx: Ratio::zero(),
+                ky: Ratio::new(-1.0),
+                kx: Ratio::new(-1.0),
+                sy: Ratio::zero(),
+                tx: h,
+                ty: w,
+            },
+        ),
+        // Make the coordinate system start at the top-left.
+        _ => (
+            Size::new(w, h),
+            Transform {
+                sx: Ratio::one(),
+                ky: Ratio::zero(),
+                kx: Ratio::zero(),
+                sy: Ratio::new(-1.0),
+                tx: Abs::zero(),
+                ty: h,
+            },
+        ),
+    }
+}
+
+/// The clockwise display rotation of a page in degrees, normalized to a
+/// multiple of 90.
+fn page_rotation(frame: &Frame) -> i32 {
+    frame
+        .items()
+        .find_map(|(_, item)| match item {
+            FrameItem::Meta(Meta::PageRotation(angle), _) => {
+                Some((angle.to_deg() / 90.0).round() as i32 * 90)
+            }
+            _ => None,
+        })
+        .map_or(0, |degrees| degrees.rem_euclid(360))
+}
+
 /// Write the page tree.
 #[tracing::instrument(skip_all)]
 pub fn write_page_tree(ctx: &mut PdfContext) {
@@ -126,6 +192,10 @@ fn write_page(ctx: &mut PdfContext, page: Page) {
     page_writer.media_box(Rect::new(0.0, 0.0, w, h));
     page_writer.contents(content_id);
 
+    if page.rotation != 0 {
+        page_writer.rotate(page.rotation);
+    }
+
     if page.uses_opacities {
         page_writer
             .group()
@@ -154,15 +224,15 @@ fn write_page(ctx: &mut PdfContext, page: Page) {
             Destination::Location(loc) => ctx.introspector.position(loc),
         };
 
-        let index = pos.page.get() - 1;
-        let y = (pos.point.y - Abs::pt(10.0)).max(Abs::zero());
-        if let Some(&height) = ctx.page_heights.get(index) {
+        if let Some((page_ref, point)) =
+            destination(&ctx.page_refs, &ctx.page_transforms, pos)
+        {
             annotation
                 .action()
                 .action_type(ActionType::GoTo)
                 .destination()
-                .page(ctx.page_refs[index])
-                .xyz(pos.point.x.to_f32(), height - y.to_f32(), None);
+                .page(page_ref)
+                .xyz(point.x.to_f32(), point.y.to_f32(), None);
         }
     }
 
@@ -174,12 +244,30 @@ fn write_page(ctx: &mut PdfContext, page: Page) {
     ctx.writer.stream(content_id, &data).filter(Filter::FlateDecode);
 }
 
+/// The page and the position in its PDF coordinate system that a link or
+/// outline item jumps to. A bit of space is left above the target.
+///
+/// Takes the context's page references and transforms rather than the context
+/// itself so that it can be called while the writer is borrowed.
+pub(super) fn destination(
+    page_refs: &[Ref],
+    page_transforms: &[Transform],
+    pos: Position,
+) -> Option<(Ref, Point)> {
+    let index = pos.page.get() - 1;
+    let ts = *page_transforms.get(index)?;
+    let y = (pos.point.y - Abs::pt(10.0)).max(Abs::zero());
+    Some((page_refs[index], Point::new(pos.point.x, y).transform(ts)))
+}
+
 /// Data for an exported page.
 pub struct Page {
     /// The indirect object id of the page.
     pub id: Ref,
-    /// The page's dimensions.
+    /// The page's physical dimensions.
     pub size: Size,
+    /// The clockwise rotation of the page for display in degrees.
+    pub rotation: i32,
     /// The page's content stream.
     pub content: Content,
     /// Whether the page uses opacities.
@@ -398,6 +486,8 @@ fn write_frame(ctx: &mut PageContext, frame: &Frame) {
                 Meta::Elem(_) => {}
                 Meta::Hide => {}
                 Meta::PageNumbering(_) => {}
+                Meta::PageRotation(_) => {}
+                Meta::Marker(_) => {}
             },
         }
     }
@@ -430,19 +520,49 @@ fn write_group(ctx: &mut PageContext, pos: Point, group: &GroupItem) {
 fn write_text(ctx: &mut PageContext, x: f32, y: f32, text: &TextItem) {
     *ctx.parent.languages.entry(text.lang).or_insert(0) += text.glyphs.len();
 
-    let glyph_set = ctx.parent.glyph_sets.entry(text.font.clone()).or_default();
+    // All instances of a variable font share the font program of its default
+    // instance. Their glyphs are drawn separately below.
+    let font = text.font.default_instance();
+    let glyph_set = ctx.parent.glyph_sets.entry(font.clone()).or_default();
     for g in &text.glyphs {
         let segment = &text.text[g.range()];
         glyph_set.entry(g.id).or_insert_with(|| segment.into());
     }
 
+    // Synthesized bold strokes the glyph outlines in the fill color.
+    let stroke = (!text.bold.is_zero()).then(|| Stroke {
+        paint: text.fill.clone(),
+        thickness: text.bold.at(text.size),
+        line_join: LineJoin::Round,
+        ..Stroke::default()
+    });
+
     ctx.set_fill(&text.fill);
-    ctx.set_font(&text.font, text.size);
-    ctx.set_opacities(None, Some(&text.fill));
+    if let Some(stroke) = &stroke {
+        ctx.set_stroke(stroke);
+    }
+    ctx.set_font(&font, text.size);
+    ctx.set_opacities(stroke.as_ref(), Some(&text.fill));
     ctx.content.begin_text();
 
-    // Positiosn the text.
-    ctx.content.set_text_matrix([1.0, 0.0, 0.0, -1.0, x, y]);
+    // The embedded font program only contains the plain outlines and, for a
+    // variable font, only the default ones. Instances and color glyphs are
+    // thus drawn separately on top of invisible text, which keeps the text
+    // extractable.
+    let drawn = !text.font.variations().is_empty()
+        || text.glyphs.iter().any(|g| is_color_glyph(&text.font, GlyphId(g.id)));
+    if drawn {
+        ctx.content.set_text_rendering_mode(TextRenderingMode::Invisible);
+    } else if stroke.is_some() {
+        ctx.content.set_text_rendering_mode(TextRenderingMode::FillStroke);
+    }
+
+    // Position the text. The text matrix also scales the glyphs horizontally
+    // for font expansion, so the adjustments must be unscaled. It also slants
+    // them for synthesized italics.
+    let scale = text.scale.get();
+    let skew = text.skew.get() as f32;
+    ctx.content.set_text_matrix([scale as f32, 0.0, skew, -1.0, x, y]);
 
     let mut positioned = ctx.content.show_positioned();
     let mut items = positioned.items();
@@ -451,7 +571,7 @@ fn write_text(ctx: &mut PageContext, x: f32, y: f32, text: &TextItem) {
 
     // Write the glyphs with kerning adjustments.
     for glyph in &text.glyphs {
-        adjustment += glyph.x_offset;
+        adjustment += glyph.x_offset / scale;
 
         if !adjustment.is_zero() {
             if !encoded.is_empty() {
@@ -466,11 +586,11 @@ fn write_text(ctx: &mut PageContext, x: f32, y: f32, text: &TextItem) {
         encoded.push((glyph.id >> 8) as u8);
         encoded.push((glyph.id & 0xff) as u8);
 
-        if let Some(advance) = text.font.advance(glyph.id) {
-            adjustment += glyph.x_advance - advance;
+        if let Some(advance) = font.advance(glyph.id) {
+            adjustment += glyph.x_advance / scale - advance;
         }
 
-        adjustment -= glyph.x_offset;
+        adjustment -= glyph.x_offset / scale;
     }
 
     if !encoded.is_empty() {
@@ -479,7 +599,194 @@ fn write_text(ctx: &mut PageContext, x: f32, y: f32, text: &TextItem) {
 
     items.finish();
     positioned.finish();
+
+    if drawn || stroke.is_some() {
+        ctx.content.set_text_rendering_mode(TextRenderingMode::Fill);
+    }
+
     ctx.content.end_text();
+
+    if drawn {
+        write_glyphs(ctx, x, y, text);
+    }
+}
+
+/// Whether a glyph has a representation other than its plain outline.
+fn is_color_glyph(font: &Font, id: GlyphId) -> bool {
+    let ttf = font.ttf();
+    ttf.glyph_svg_image(id).is_some()
+        || font.color_layers(id.0, Color::BLACK).is_some()
+        || ttf.glyph_raster_image(id, u16::MAX).is_some()
+}
+
+/// Draw the glyphs of a text item as SVG images, color layers, bitmaps or
+/// paths.
+fn write_glyphs(ctx: &mut PageContext, x: f32, y: f32, text: &TextItem) {
+    let mut offset = 0.0;
+    for glyph in &text.glyphs {
+        let id = GlyphId(glyph.id);
+        let x = x + offset + glyph.x_offset.at(text.size).to_f32();
+
+        write_svg_glyph(ctx, x, y, text, id)
+            .or_else(|| write_colr_glyph(ctx, x, y, text, id))
+            .or_else(|| write_bitmap_glyph(ctx, x, y, text, id))
+            .or_else(|| write_outline_glyph(ctx, x, y, text, id, &text.fill));
+
+        offset += glyph.x_advance.at(text.size).to_f32();
+    }
+}
+
+/// Draw an SVG glyph as an image.
+fn write_svg_glyph(
+    ctx: &mut PageContext,
+    x: f32,
+    y: f32,
+    text: &TextItem,
+    id: GlyphId,
+) -> Option<()> {
+    let svg = convert_svg_glyph(&text.font, id)?;
+    let format = ImageFormat::Vector(VectorFormat::Svg);
+    let image = Image::new(svg.into_bytes().into(), format, None).ok()?;
+
+    // The glyph's view box spans the em square, starting at the ascender.
+    let ascender = text.font.metrics().ascender.at(text.size).to_f32();
+    let size = Size::new(text.size * text.scale.get(), text.size);
+    write_image(ctx, x, y - ascender, &image, size);
+    Some(())
+}
+
+/// Draw a layered color glyph as paths.
+fn write_colr_glyph(
+    ctx: &mut PageContext,
+    x: f32,
+    y: f32,
+    text: &TextItem,
+    id: GlyphId,
+) -> Option<()> {
+    let Paint::Solid(fill) = text.fill;
+    for (layer, color) in text.font.color_layers(id.0, fill)? {
+        write_outline_glyph(ctx, x, y, text, GlyphId(layer), &Paint::Solid(color));
+    }
+    Some(())
+}
+
+/// Draw a bitmap glyph as an image.
+fn write_bitmap_glyph(
+    ctx: &mut PageContext,
+    x: f32,
+    y: f32,
+    text: &TextItem,
+    id: GlyphId,
+) -> Option<()> {
+    let raster = text.font.ttf().glyph_raster_image(id, u16::MAX)?;
+    let image =
+        Image::new(raster.data.into(), raster.format.try_into().ok()?, None).ok()?;
+
+    // Positioned like in the raster export.
+    let size = text.size.to_f32();
+    let h = text.size;
+    let w = (image.width() as f64 / image.height() as f64) * h * text.scale.get();
+    let dx = (raster.x as f32) / (image.width() as f32) * size;
+    let dy = (raster.y as f32) / (image.height() as f32) * size;
+    write_image(ctx, x + dx, y - size - dy, &image, Size::new(w, h));
+    Some(())
+}
+
+/// Draw a glyph's outline as a filled path.
+fn write_outline_glyph(
+    ctx: &mut PageContext,
+    x: f32,
+    y: f32,
+    text: &TextItem,
+    id: GlyphId,
+    fill: &Paint,
+) -> Option<()> {
+    let size = text.size.to_f32() / text.font.units_per_em() as f32;
+    let stroke = (!text.bold.is_zero()).then(|| Stroke {
+        paint: fill.clone(),
+        thickness: text.bold.at(text.size),
+        line_join: LineJoin::Round,
+        ..Stroke::default()
+    });
+
+    ctx.set_fill(fill);
+    if let Some(stroke) = &stroke {
+        ctx.set_stroke(stroke);
+    }
+    ctx.set_opacities(stroke.as_ref(), Some(fill));
+
+    let mut writer = OutlineWriter {
+        content: &mut ctx.content,
+        origin: (x, y),
+        scale: (size * text.scale.get() as f32, size),
+        skew: text.skew.get() as f32,
+        last: (0.0, 0.0),
+    };
+
+    text.font.ttf().outline_glyph(id, &mut writer)?;
+    if stroke.is_some() {
+        ctx.content.fill_nonzero_and_stroke();
+    } else {
+        ctx.content.fill_nonzero();
+    }
+    Some(())
+}
+
+/// Writes glyph outlines in font units into a content stream.
+struct OutlineWriter<'a> {
+    content: &'a mut Content,
+    /// Where the glyph's origin is on the page.
+    origin: (f32, f32),
+    /// How to scale font units horizontally and vertically.
+    scale: (f32, f32),
+    /// How far to slant the outline to the right per unit of height.
+    skew: f32,
+    /// The current point in font units.
+    last: (f32, f32),
+}
+
+impl OutlineWriter<'_> {
+    /// Map a point from font units to page coordinates, flipping the y-axis.
+    fn map(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            self.origin.0 + x * self.scale.0 + y * self.skew * self.scale.1,
+            self.origin.1 - y * self.scale.1,
+        )
+    }
+}
+
+impl OutlineBuilder for OutlineWriter<'_> {
+    fn move_to(&mut self, x: f32, y: f32) {
+        let (px, py) = self.map(x, y);
+        self.content.move_to(px, py);
+        self.last = (x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let (px, py) = self.map(x, y);
+        self.content.line_to(px, py);
+        self.last = (x, y);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        // Elevate the quadratic curve to a cubic one.
+        let (x0, y0) = self.last;
+        let c1 = (x0 + 2.0 / 3.0 * (x1 - x0), y0 + 2.0 / 3.0 * (y1 - y0));
+        let c2 = (x + 2.0 / 3.0 * (x1 - x), y + 2.0 / 3.0 * (y1 - y));
+        self.curve_to(c1.0, c1.1, c2.0, c2.1, x, y);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (p1x, p1y) = self.map(x1, y1);
+        let (p2x, p2y) = self.map(x2, y2);
+        let (px, py) = self.map(x, y);
+        self.content.cubic_to(p1x, p1y, p2x, p2y, px, py);
+        self.last = (x, y);
+    }
+
+    fn close(&mut self) {
+        self.content.close_path();
+    }
 }
 
 /// Encode a geometrical shape into the content stream.
@@ -14,8 +14,8 @@ use usvg::{NodeExt, TreeParsing};
 use crate::doc::{Frame, FrameItem, GroupItem, Meta, TextItem};
 use crate::font::Font;
 use crate::geom::{
-    self, Abs, Color, Geometry, LineCap, LineJoin, Paint, PathItem, Shape, Size, Stroke,
-    Transform,
+    self, Abs, Color, Geometry, LineCap, LineJoin, Numeric, Paint, PathItem, Shape, Size,
+    Stroke, Transform,
 };
 use crate::image::{DecodedImage, Image};
 
@@ -107,6 +107,8 @@ fn render_frame(
                 Meta::Link(_) => {}
                 Meta::Elem(_) => {}
                 Meta::PageNumbering(_) => {}
+                Meta::PageRotation(_) => {}
+                Meta::Marker(_) => {}
                 Meta::Hide => {}
             },
         }
@@ -177,9 +179,20 @@ fn render_text(
     for glyph in &text.glyphs {
         let id = GlyphId(glyph.id);
         let offset = x + glyph.x_offset.at(text.size).to_f32();
-        let ts = ts.pre_translate(offset, 0.0);
+        let ts = ts
+            .pre_translate(offset, 0.0)
+            .pre_scale(text.scale.get() as f32, 1.0)
+            .pre_concat(sk::Transform::from_row(
+                1.0,
+                0.0,
+                -text.skew.get() as f32,
+                1.0,
+                0.0,
+                0.0,
+            ));
 
         render_svg_glyph(canvas, ts, mask, text, id)
+            .or_else(|| render_colr_glyph(canvas, ts, mask, text, id))
             .or_else(|| render_bitmap_glyph(canvas, ts, mask, text, id))
             .or_else(|| render_outline_glyph(canvas, ts, mask, text, id));
 
@@ -195,7 +208,7 @@ fn render_svg_glyph(
     text: &TextItem,
     id: GlyphId,
 ) -> Option<()> {
-    let mut data = text.font.ttf().glyph_svg_image(id)?;
+    let mut data = text.font.ttf().glyph_svg_image(id)?.data;
 
     // Decompress SVGZ.
     let mut decoded = vec![];
@@ -284,6 +297,36 @@ fn render_svg_glyph(
     Some(())
 }
 
+/// Render a layered color glyph into the canvas.
+fn render_colr_glyph(
+    canvas: &mut sk::Pixmap,
+    ts: sk::Transform,
+    mask: Option<&sk::Mask>,
+    text: &TextItem,
+    id: GlyphId,
+) -> Option<()> {
+    let Paint::Solid(fill) = text.fill;
+    let layers = text.font.color_layers(id.0, fill)?;
+
+    // Flip vertically because font design coordinate
+    // system is Y-up.
+    let scale = text.size.to_f32() / text.font.units_per_em() as f32;
+    let ts = ts.pre_scale(scale, -scale);
+
+    for (layer, color) in layers {
+        let mut builder = WrappedPathBuilder(sk::PathBuilder::new());
+        if text.font.ttf().outline_glyph(GlyphId(layer), &mut builder).is_none() {
+            continue;
+        }
+
+        let Some(path) = builder.0.finish() else { continue };
+        let paint = (&Paint::Solid(color)).into();
+        canvas.fill_path(&path, &paint, sk::FillRule::default(), ts, mask);
+    }
+
+    Some(())
+}
+
 /// Render a bitmap glyph into the canvas.
 fn render_bitmap_glyph(
     canvas: &mut sk::Pixmap,
@@ -295,7 +338,8 @@ fn render_bitmap_glyph(
     let size = text.size.to_f32();
     let ppem = size * ts.sy;
     let raster = text.font.ttf().glyph_raster_image(id, ppem as u16)?;
-    let image = Image::new(raster.data.into(), raster.format.into(), None).ok()?;
+    let image =
+        Image::new(raster.data.into(), raster.format.try_into().ok()?, None).ok()?;
 
     // FIXME: Vertical alignment isn't quite right for Apple Color Emoji,
     // and maybe also for Noto Color Emoji. And: Is the size calculation
@@ -319,9 +363,14 @@ fn render_outline_glyph(
     let ppem = text.size.to_f32() * ts.sy;
 
     // Render a glyph directly as a path. This only happens when the fast glyph
-    // rasterization can't be used due to very large text size or weird
-    // scale/skewing transforms.
-    if ppem > 100.0 || ts.kx != 0.0 || ts.ky != 0.0 || ts.sx != ts.sy {
+    // rasterization can't be used due to very large text size, weird
+    // scale/skewing transforms or synthesized bold.
+    if ppem > 100.0
+        || ts.kx != 0.0
+        || ts.ky != 0.0
+        || ts.sx != ts.sy
+        || !text.bold.is_zero()
+    {
         let path = {
             let mut builder = WrappedPathBuilder(sk::PathBuilder::new());
             text.font.ttf().outline_glyph(id, &mut builder)?;
@@ -336,6 +385,18 @@ fn render_outline_glyph(
         let scale = text.size.to_f32() / text.font.units_per_em() as f32;
         let ts = ts.pre_scale(scale, -scale);
         canvas.fill_path(&path, &paint, rule, ts, mask);
+
+        // Embolden the glyph with a stroke around its outline. The stroke is
+        // in font units because of the transform.
+        if !text.bold.is_zero() {
+            let stroke = sk::Stroke {
+                width: (text.bold.get() * text.font.units_per_em()) as f32,
+                line_join: sk::LineJoin::Round,
+                ..Default::default()
+            };
+            canvas.stroke_path(&path, &paint, &stroke, ts, mask);
+        }
+
         return Some(());
     }
 
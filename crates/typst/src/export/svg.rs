@@ -10,8 +10,8 @@ use xmlwriter::XmlWriter;
 use crate::doc::{Frame, FrameItem, GroupItem, TextItem};
 use crate::font::Font;
 use crate::geom::{
-    Abs, Axes, Geometry, LineCap, LineJoin, Paint, PathItem, Ratio, Shape, Size, Stroke,
-    Transform,
+    Abs, Axes, Color, Geometry, LineCap, LineJoin, Numeric, Paint, PathItem, Ratio,
+    Shape, Size, Stroke, Transform,
 };
 use crate::image::{Image, ImageFormat, RasterFormat, VectorFormat};
 use crate::util::hash128;
@@ -70,6 +70,9 @@ enum RenderedGlyph {
     ///
     /// The url is in the format of `data:image/{format};base64,`.
     Image { url: EcoString, width: f64, height: f64, ts: Transform },
+    /// Layers of a color glyph, each a path with its color. Layers without a
+    /// color are filled with the text's color.
+    Layers(Vec<(EcoString, Color)>),
 }
 
 impl SVGRenderer {
@@ -151,25 +154,43 @@ impl SVGRenderer {
     }
 
     /// Render a text item. The text is rendered as a group of glyphs. We will
-    /// try to render the text as SVG first, then bitmap, then outline. If none
-    /// of them works, we will skip the text.
+    /// try to render the text as SVG first, then as color layers, then bitmap,
+    /// then outline. If none of them works, we will skip the text.
     fn render_text(&mut self, text: &TextItem) {
         let scale: f64 = text.size.to_pt() / text.font.units_per_em();
         let inv_scale: f64 = text.font.units_per_em() / text.size.to_pt();
 
+        // The horizontal scale for font expansion. Glyph positions already
+        // include it, so they must be unscaled within the group.
+        let expansion = text.scale.get();
+
         self.xml.start_element("g");
         self.xml.write_attribute("class", "typst-text");
-        self.xml.write_attribute_fmt(
-            "transform",
-            format_args!("scale({} {})", scale, -scale),
-        );
+        if text.skew.is_zero() {
+            self.xml.write_attribute_fmt(
+                "transform",
+                format_args!("scale({} {})", scale * expansion, -scale),
+            );
+        } else {
+            // Slant the glyphs for synthesized italics.
+            self.xml.write_attribute_fmt(
+                "transform",
+                format_args!(
+                    "matrix({} 0 {} {} 0 0)",
+                    scale * expansion,
+                    scale * text.skew.get(),
+                    -scale
+                ),
+            );
+        }
 
         let mut x: f64 = 0.0;
         for glyph in &text.glyphs {
             let id = GlyphId(glyph.id);
-            let offset = x + glyph.x_offset.at(text.size).to_pt();
+            let offset = (x + glyph.x_offset.at(text.size).to_pt()) / expansion;
 
             self.render_svg_glyph(text, id, offset, inv_scale)
+                .or_else(|| self.render_colr_glyph(text, id, offset, inv_scale))
                 .or_else(|| self.render_bitmap_glyph(text, id, offset, inv_scale))
                 .or_else(|| self.render_outline_glyph(text, id, offset, inv_scale));
 
@@ -209,6 +230,29 @@ impl SVGRenderer {
         Some(())
     }
 
+    /// Render a glyph defined by color layers.
+    fn render_colr_glyph(
+        &mut self,
+        text: &TextItem,
+        id: GlyphId,
+        x_offset: f64,
+        inv_scale: f64,
+    ) -> Option<()> {
+        let Paint::Solid(fill) = text.fill;
+        let layers = convert_colr_glyph_to_layers(&text.font, id, fill)?;
+        let hash = hash128(&(&text.font, id, fill));
+        let id = self.glyphs.insert_with(hash, || RenderedGlyph::Layers(layers));
+
+        self.xml.start_element("use");
+        self.xml.write_attribute_fmt("xlink:href", format_args!("#{id}"));
+        self.xml
+            .write_attribute_fmt("x", format_args!("{}", x_offset * inv_scale));
+        self.write_fill(&text.fill);
+        self.xml.end_element();
+
+        Some(())
+    }
+
     /// Render a glyph defined by a bitmap.
     fn render_bitmap_glyph(
         &mut self,
@@ -271,6 +315,17 @@ impl SVGRenderer {
         self.xml
             .write_attribute_fmt("x", format_args!("{}", x_offset * inv_scale));
         self.write_fill(&text.fill);
+
+        // Embolden the glyph with a stroke around its outline for synthesized
+        // bold. The stroke is in font units because of the group's transform.
+        if !text.bold.is_zero() {
+            let Paint::Solid(color) = &text.fill;
+            let width = text.bold.get() * text.font.units_per_em();
+            self.xml.write_attribute("stroke", &color.to_rgba().to_hex());
+            self.xml.write_attribute("stroke-width", &width);
+            self.xml.write_attribute("stroke-linejoin", "round");
+        }
+
         self.xml.end_element();
 
         Some(())
@@ -383,6 +438,14 @@ impl SVGRenderer {
                     self.xml.write_attribute("preserveAspectRatio", "none");
                     self.xml.end_element();
                 }
+                RenderedGlyph::Layers(layers) => {
+                    for (path, color) in layers {
+                        self.xml.start_element("path");
+                        self.xml.write_attribute("d", &path);
+                        self.xml.write_attribute("fill", &color.to_rgba().to_hex());
+                        self.xml.end_element();
+                    }
+                }
             }
 
             self.xml.end_element();
@@ -417,18 +480,51 @@ fn convert_outline_glyph_to_path(font: &Font, id: GlyphId) -> Option<EcoString>
     Some(builder.0)
 }
 
+/// Convert the layers of a color glyph to SVG paths.
+#[comemo::memoize]
+fn convert_colr_glyph_to_layers(
+    font: &Font,
+    id: GlyphId,
+    foreground: Color,
+) -> Option<Vec<(EcoString, Color)>> {
+    let layers = font.color_layers(id.0, foreground)?;
+    Some(
+        layers
+            .into_iter()
+            .filter_map(|(layer, color)| {
+                let path = convert_outline_glyph_to_path(font, GlyphId(layer))?;
+                Some((path, color))
+            })
+            .collect(),
+    )
+}
+
 /// Convert a bitmap glyph to an encoded image URL.
 #[comemo::memoize]
 fn convert_bitmap_glyph_to_image(font: &Font, id: GlyphId) -> Option<(Image, f64, f64)> {
     let bitmap = font.ttf().glyph_raster_image(id, std::u16::MAX)?;
-    let image = Image::new(bitmap.data.into(), bitmap.format.into(), None).ok()?;
+    let image =
+        Image::new(bitmap.data.into(), bitmap.format.try_into().ok()?, None).ok()?;
     Some((image, bitmap.x as f64, bitmap.y as f64))
 }
 
 /// Convert an SVG glyph to an encoded image URL.
 #[comemo::memoize]
 fn convert_svg_glyph_to_base64_url(font: &Font, id: GlyphId) -> Option<EcoString> {
-    let mut data = font.ttf().glyph_svg_image(id)?;
+    let svg_str = convert_svg_glyph(font, id)?;
+    let mut url: EcoString = "data:image/svg+xml;base64,".into();
+    let b64_encoded =
+        base64::engine::general_purpose::STANDARD.encode(svg_str.as_bytes());
+    url.push_str(&b64_encoded);
+
+    Some(url)
+}
+
+/// Extract the SVG document of an SVG glyph, adding a view box that spans the
+/// em square if it has none.
+#[comemo::memoize]
+pub(super) fn convert_svg_glyph(font: &Font, id: GlyphId) -> Option<String> {
+    let mut data = font.ttf().glyph_svg_image(id)?.data;
 
     // Decompress SVGZ.
     let mut decoded = vec![];
@@ -478,12 +574,7 @@ fn convert_svg_glyph_to_base64_url(font: &Font, id: GlyphId) -> Option<EcoString
         );
     }
 
-    let mut url: EcoString = "data:image/svg+xml;base64,".into();
-    let b64_encoded =
-        base64::engine::general_purpose::STANDARD.encode(svg_str.as_bytes());
-    url.push_str(&b64_encoded);
-
-    Some(url)
+    Some(svg_str)
 }
 
 /// Convert a geometry to an SVG path.
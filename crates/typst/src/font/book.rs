@@ -6,6 +6,7 @@ use ttf_parser::{name_id, PlatformId, Tag};
 use unicode_segmentation::UnicodeSegmentation;
 
 use super::{Font, FontStretch, FontStyle, FontVariant, FontWeight};
+use crate::geom::Ratio;
 
 /// Metadata about a collection of fonts.
 #[derive(Default, Clone, Hash)]
@@ -170,6 +171,36 @@ pub struct FontInfo {
     pub flags: FontFlags,
     /// The unicode coverage of the font.
     pub coverage: Coverage,
+    /// The variation axis values of this instance of a variable font. Empty
+    /// for static fonts and for the default instance of a variable font.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variations: Vec<FontVariation>,
+}
+
+/// A value for a variation axis of a variable font.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct FontVariation {
+    /// The axis tag, like `wght`.
+    pub tag: [u8; 4],
+    /// The axis value in 16.16 fixed point format.
+    pub fixed: i32,
+}
+
+impl FontVariation {
+    /// Create a new variation from an axis tag and value.
+    pub fn new(tag: [u8; 4], value: f32) -> Self {
+        Self { tag, fixed: (value * 65536.0).round() as i32 }
+    }
+
+    /// The axis tag.
+    pub fn tag(self) -> Tag {
+        Tag::from_bytes(&self.tag)
+    }
+
+    /// The axis value.
+    pub fn value(self) -> f32 {
+        self.fixed as f32 / 65536.0
+    }
 }
 
 bitflags::bitflags! {
@@ -195,6 +226,93 @@ impl FontInfo {
         })
     }
 
+    /// Compute metadata for all fonts in the given data together with their
+    /// index in the collection.
+    ///
+    /// Unlike [`iter`](Self::iter), this yields one entry per named instance
+    /// for variable fonts instead of one for the default instance.
+    pub fn iter_instances(data: &[u8]) -> impl Iterator<Item = (u32, FontInfo)> + '_ {
+        let count = ttf_parser::fonts_in_collection(data).unwrap_or(1);
+        (0..count).flat_map(move |index| {
+            let infos = ttf_parser::Face::parse(data, index)
+                .ok()
+                .map(|ttf| Self::instances(&ttf))
+                .unwrap_or_default();
+            infos.into_iter().map(move |info| (index, info))
+        })
+    }
+
+    /// Compute metadata for all named instances of a ttf-parser face, or just
+    /// the face itself if it is not a variable font.
+    pub(super) fn instances(ttf: &ttf_parser::Face) -> Vec<Self> {
+        let Some(base) = Self::from_ttf(ttf) else { return vec![] };
+        let instances = named_instances(ttf);
+        if instances.is_empty() {
+            return vec![base];
+        }
+
+        // Axis values at their defaults are left out, so that the instance
+        // matching the default one is equal to the font itself.
+        let axes = ttf.variation_axes();
+        instances
+            .into_iter()
+            .map(|(name_id, mut variations)| {
+                variations.retain(|var| {
+                    axes.into_iter().any(|axis| {
+                        axis.tag == var.tag() && axis.def_value != var.value()
+                    })
+                });
+
+                let mut info = base.vary(&variations);
+                if let Some(mut name) = find_name(ttf, name_id) {
+                    name.make_ascii_lowercase();
+                    if name.contains("italic") {
+                        info.variant.style = FontStyle::Italic;
+                    } else if name.contains("oblique") || name.contains("slanted") {
+                        info.variant.style = FontStyle::Oblique;
+                    }
+                }
+                info
+            })
+            .collect()
+    }
+
+    /// The metadata for an instance of this font with the given variation
+    /// axis values.
+    ///
+    /// The variant is derived from the registered `wght`, `wdth`, `ital` and
+    /// `slnt` axes.
+    pub(super) fn vary(&self, variations: &[FontVariation]) -> Self {
+        let mut info = self.clone();
+        for var in variations {
+            let value = var.value();
+            match &var.tag {
+                b"wght" => {
+                    info.variant.weight =
+                        FontWeight::from_number(value.clamp(1.0, 1000.0).round() as u16)
+                }
+                b"wdth" => {
+                    info.variant.stretch =
+                        FontStretch::from_ratio(Ratio::new(value as f64 / 100.0))
+                }
+                b"ital" => {
+                    if value >= 0.5 {
+                        info.variant.style = FontStyle::Italic;
+                    }
+                }
+                b"slnt" => {
+                    if value != 0.0 && info.variant.style == FontStyle::Normal {
+                        info.variant.style = FontStyle::Oblique;
+                    }
+                }
+                _ => {}
+            }
+        }
+        info.variations = variations.to_vec();
+        info.variations.sort_by_key(|var| var.tag);
+        info
+    }
+
     /// Compute metadata for a single ttf-parser face.
     pub(super) fn from_ttf(ttf: &ttf_parser::Face) -> Option<Self> {
         // We cannot use Name ID 16 "Typographic Family", because for some
@@ -282,10 +400,60 @@ impl FontInfo {
             variant,
             flags,
             coverage: Coverage::from_vec(codepoints),
+            variations: vec![],
         })
     }
 }
 
+/// Read the subfamily name ids and axis values of the named instances from
+/// the `fvar` table of a variable font.
+fn named_instances(ttf: &ttf_parser::Face) -> Vec<(u16, Vec<FontVariation>)> {
+    let Some(fvar) = ttf.raw_face().table(Tag::from_bytes(b"fvar")) else {
+        return vec![];
+    };
+
+    let u16_at = |offset: usize| {
+        fvar.get(offset..offset + 2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+    };
+    let i32_at = |offset: usize| {
+        fvar.get(offset..offset + 4)
+            .map(|b| i32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    };
+
+    let (Some(axes_offset), Some(axis_count), Some(axis_size), Some(count), Some(size)) =
+        (u16_at(4), u16_at(8), u16_at(10), u16_at(12), u16_at(14))
+    else {
+        return vec![];
+    };
+
+    let axes_offset = usize::from(axes_offset);
+    let axis_size = usize::from(axis_size);
+    let tags: Option<Vec<[u8; 4]>> = (0..usize::from(axis_count))
+        .map(|i| {
+            let start = axes_offset + i * axis_size;
+            fvar.get(start..start + 4).map(|b| [b[0], b[1], b[2], b[3]])
+        })
+        .collect();
+    let Some(tags) = tags else { return vec![] };
+
+    let instances_offset = axes_offset + tags.len() * axis_size;
+    (0..usize::from(count))
+        .map_while(|i| {
+            let start = instances_offset + i * usize::from(size);
+            let name_id = u16_at(start)?;
+            let variations = tags
+                .iter()
+                .enumerate()
+                .map(|(j, &tag)| {
+                    let fixed = i32_at(start + 4 + 4 * j)?;
+                    Some(FontVariation { tag, fixed })
+                })
+                .collect::<Option<_>>()?;
+            Some((name_id, variations))
+        })
+        .collect()
+}
+
 /// Try to find and decode the name with the given id.
 pub(super) fn find_name(ttf: &ttf_parser::Face, name_id: u16) -> Option<String> {
     ttf.names().into_iter().find_map(|entry| {
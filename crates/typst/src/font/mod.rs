@@ -3,18 +3,18 @@
 mod book;
 mod variant;
 
-pub use self::book::{Coverage, FontBook, FontFlags, FontInfo};
+pub use self::book::{Coverage, FontBook, FontFlags, FontInfo, FontVariation};
 pub use self::variant::{FontStretch, FontStyle, FontVariant, FontWeight};
 
 use std::fmt::{self, Debug, Formatter};
 use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 
-use ttf_parser::GlyphId;
+use ttf_parser::{GlyphId, Tag};
 
 use self::book::find_name;
 use crate::eval::{Bytes, Cast};
-use crate::geom::Em;
+use crate::geom::{Color, Em, RgbaColor};
 
 /// An OpenType font.
 ///
@@ -42,7 +42,71 @@ struct Repr {
 
 impl Font {
     /// Parse a font from data and collection index.
+    ///
+    /// For variable fonts, this is the default instance.
     pub fn new(data: Bytes, index: u32) -> Option<Self> {
+        let ttf = ttf_parser::Face::parse(&data, index).ok()?;
+        let info = FontInfo::from_ttf(&ttf)?;
+        Self::instantiate(data, index, info)
+    }
+
+    /// Parse all fonts in the given data.
+    ///
+    /// For variable fonts with named instances, this yields one font per
+    /// instance.
+    pub fn iter(data: Bytes) -> impl Iterator<Item = Self> {
+        let count = ttf_parser::fonts_in_collection(&data).unwrap_or(1);
+        (0..count).flat_map(move |index| {
+            let infos = ttf_parser::Face::parse(&data, index)
+                .ok()
+                .map(|ttf| FontInfo::instances(&ttf))
+                .unwrap_or_default();
+            let data = data.clone();
+            infos
+                .into_iter()
+                .filter_map(move |info| Self::instantiate(data.clone(), index, info))
+        })
+    }
+
+    /// Create an instance of this font with the given variation axis values.
+    ///
+    /// The values override this font's existing ones per axis. Axes that the
+    /// font doesn't have are ignored and values are clamped to the axis range.
+    /// Values at an axis' default are dropped, so that the default instance is
+    /// always equal to the font created with [`new`](Self::new).
+    pub fn with_variations(&self, variations: &[FontVariation]) -> Self {
+        let mut merged = self.0.info.variations.clone();
+        for var in variations {
+            let Some(axis) = self
+                .ttf()
+                .variation_axes()
+                .into_iter()
+                .find(|axis| axis.tag == var.tag())
+            else {
+                continue;
+            };
+
+            let value = var.value().clamp(axis.min_value, axis.max_value);
+            let var = FontVariation::new(var.tag, value);
+            merged.retain(|prev| prev.tag != var.tag);
+            if value != axis.def_value {
+                merged.push(var);
+            }
+        }
+
+        merged.sort_by_key(|var| var.tag);
+        if merged == self.0.info.variations {
+            return self.clone();
+        }
+
+        let info = self.0.info.vary(&merged);
+        Self::instantiate(self.0.data.clone(), self.0.index, info)
+            .unwrap_or_else(|| self.clone())
+    }
+
+    /// Parse the font's faces and set them to the variation axis values
+    /// given in the info.
+    fn instantiate(data: Bytes, index: u32, info: FontInfo) -> Option<Self> {
         // Safety:
         // - The slices's location is stable in memory:
         //   - We don't move the underlying vector
@@ -52,20 +116,25 @@ impl Font {
         let slice: &'static [u8] =
             unsafe { std::slice::from_raw_parts(data.as_ptr(), data.len()) };
 
-        let ttf = ttf_parser::Face::parse(slice, index).ok()?;
-        let rusty = rustybuzz::Face::from_slice(slice, index)?;
-        let metrics = FontMetrics::from_ttf(&ttf);
-        let info = FontInfo::from_ttf(&ttf)?;
+        let mut ttf = ttf_parser::Face::parse(slice, index).ok()?;
+        let mut rusty = rustybuzz::Face::from_slice(slice, index)?;
+        if !info.variations.is_empty() {
+            for var in &info.variations {
+                ttf.set_variation(var.tag(), var.value());
+            }
+
+            let variations: Vec<_> = info
+                .variations
+                .iter()
+                .map(|var| rustybuzz::Variation { tag: var.tag(), value: var.value() })
+                .collect();
+            rusty.set_variations(&variations);
+        }
 
+        let metrics = FontMetrics::from_ttf(&ttf);
         Some(Self(Arc::new(Repr { data, index, info, metrics, ttf, rusty })))
     }
 
-    /// Parse all fonts in the given data.
-    pub fn iter(data: Bytes) -> impl Iterator<Item = Self> {
-        let count = ttf_parser::fonts_in_collection(&data).unwrap_or(1);
-        (0..count).filter_map(move |index| Self::new(data.clone(), index))
-    }
-
     /// The underlying buffer.
     pub fn data(&self) -> &Bytes {
         &self.0.data
@@ -81,6 +150,46 @@ impl Font {
         &self.0.info
     }
 
+    /// The variation axis values this font is instantiated with. Empty for
+    /// static fonts and the default instance of a variable font.
+    pub fn variations(&self) -> &[FontVariation] {
+        &self.0.info.variations
+    }
+
+    /// The current value of the variation axis with the given tag, or `None`
+    /// if the font doesn't have such an axis.
+    pub fn variation(&self, tag: [u8; 4]) -> Option<f32> {
+        let axis = self
+            .0
+            .ttf
+            .variation_axes()
+            .into_iter()
+            .find(|axis| axis.tag == Tag::from_bytes(&tag))?;
+
+        Some(
+            self.variations()
+                .iter()
+                .find(|var| var.tag == tag)
+                .map_or(axis.def_value, |var| var.value()),
+        )
+    }
+
+    /// Whether the font has variation axes.
+    pub fn is_variable(&self) -> bool {
+        self.0.ttf.is_variable()
+    }
+
+    /// The default instance of this font's face. For static fonts and default
+    /// instances, this is the font itself.
+    pub fn default_instance(&self) -> Self {
+        if self.variations().is_empty() {
+            return self.clone();
+        }
+
+        default_instance(self.0.data.clone(), self.0.index)
+            .unwrap_or_else(|| self.clone())
+    }
+
     /// The font's metrics.
     pub fn metrics(&self) -> &FontMetrics {
         &self.0.metrics
@@ -104,6 +213,33 @@ impl Font {
             .map(|units| self.to_em(units))
     }
 
+    /// Look up the layers of a color glyph in the font's `COLR` table.
+    ///
+    /// Each layer is a glyph whose outline is filled with the layer's color
+    /// from the first palette. Layers without a palette color are filled with
+    /// the `foreground` color.
+    ///
+    /// Only glyphs made of solidly filled layers like in version 0 of the
+    /// table are supported. For glyphs that use the gradients, transforms,
+    /// clips or compositing of version 1, this returns `None`, so that their
+    /// plain outline is used instead.
+    pub fn color_layers(
+        &self,
+        glyph: u16,
+        foreground: Color,
+    ) -> Option<Vec<(u16, Color)>> {
+        let id = GlyphId(glyph);
+        if !self.0.ttf.is_color_glyph(id) {
+            return None;
+        }
+
+        let RgbaColor { r, g, b, a } = foreground.to_rgba();
+        let foreground = ttf_parser::RgbaColor::new(r, g, b, a);
+        let mut painter = LayerPainter { layers: vec![], current: None, simple: true };
+        self.0.ttf.paint_color_glyph(id, 0, foreground, &mut painter)?;
+        painter.simple.then_some(painter.layers)
+    }
+
     /// Lookup a name by id.
     pub fn find_name(&self, id: u16) -> Option<String> {
         find_name(&self.0.ttf, id)
@@ -124,10 +260,17 @@ impl Font {
     }
 }
 
+/// Parse the default instance of a variable font.
+#[comemo::memoize]
+fn default_instance(data: Bytes, index: u32) -> Option<Font> {
+    Font::new(data, index)
+}
+
 impl Hash for Font {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.0.data.hash(state);
         self.0.index.hash(state);
+        self.0.info.variations.hash(state);
     }
 }
 
@@ -141,8 +284,73 @@ impl Eq for Font {}
 
 impl PartialEq for Font {
     fn eq(&self, other: &Self) -> bool {
-        self.0.data == other.0.data && self.0.index == other.0.index
+        self.0.data == other.0.data
+            && self.0.index == other.0.index
+            && self.0.info.variations == other.0.info.variations
+    }
+}
+
+/// Collects the layers of a `COLR` glyph.
+struct LayerPainter {
+    layers: Vec<(u16, Color)>,
+    current: Option<GlyphId>,
+    /// Whether the glyph only consists of solidly filled layers.
+    simple: bool,
+}
+
+impl ttf_parser::colr::Painter<'_> for LayerPainter {
+    fn outline_glyph(&mut self, glyph_id: GlyphId) {
+        self.current = Some(glyph_id);
+    }
+
+    fn paint(&mut self, paint: ttf_parser::colr::Paint) {
+        let ttf_parser::colr::Paint::Solid(color) = paint else {
+            self.simple = false;
+            return;
+        };
+        if let Some(id) = self.current.take() {
+            let color = RgbaColor::new(color.red, color.green, color.blue, color.alpha);
+            self.layers.push((id.0, color.into()));
+        }
+    }
+
+    fn push_clip(&mut self) {
+        self.simple = false;
     }
+
+    fn push_clip_box(&mut self, _: ttf_parser::colr::ClipBox) {
+        self.simple = false;
+    }
+
+    fn pop_clip(&mut self) {}
+
+    fn push_layer(&mut self, _: ttf_parser::colr::CompositeMode) {
+        self.simple = false;
+    }
+
+    fn pop_layer(&mut self) {}
+
+    fn push_translate(&mut self, _: f32, _: f32) {
+        self.simple = false;
+    }
+
+    fn push_scale(&mut self, _: f32, _: f32) {
+        self.simple = false;
+    }
+
+    fn push_rotate(&mut self, _: f32) {
+        self.simple = false;
+    }
+
+    fn push_skew(&mut self, _: f32, _: f32) {
+        self.simple = false;
+    }
+
+    fn push_transform(&mut self, _: ttf_parser::Transform) {
+        self.simple = false;
+    }
+
+    fn pop_transform(&mut self) {}
 }
 
 /// Metrics of a font.
@@ -190,7 +190,13 @@ impl Resolve for GenAlign {
     type Output = Align;
 
     fn resolve(self, styles: StyleChain) -> Self::Output {
-        let dir = item!(dir)(styles);
+        // Start and end are horizontal. Vertical text lays its lines out along
+        // the inline axis from left to right before turning them, so start
+        // and end refer to that axis.
+        let dir = match item!(dir)(styles) {
+            Dir::TTB | Dir::BTT => Dir::LTR,
+            dir => dir,
+        };
         match self {
             Self::Start => dir.start().into(),
             Self::End => dir.end().into(),
@@ -215,17 +215,22 @@ impl TryFrom<image::ImageFormat> for RasterFormat {
     }
 }
 
-impl From<ttf_parser::RasterImageFormat> for RasterFormat {
-    fn from(format: ttf_parser::RasterImageFormat) -> Self {
-        match format {
+impl TryFrom<ttf_parser::RasterImageFormat> for RasterFormat {
+    type Error = EcoString;
+
+    fn try_from(format: ttf_parser::RasterImageFormat) -> StrResult<Self> {
+        Ok(match format {
             ttf_parser::RasterImageFormat::PNG => RasterFormat::Png,
-        }
+            _ => bail!("Format not yet supported."),
+        })
     }
 }
 
-impl From<ttf_parser::RasterImageFormat> for ImageFormat {
-    fn from(format: ttf_parser::RasterImageFormat) -> Self {
-        Self::Raster(format.into())
+impl TryFrom<ttf_parser::RasterImageFormat> for ImageFormat {
+    type Error = EcoString;
+
+    fn try_from(format: ttf_parser::RasterImageFormat) -> StrResult<Self> {
+        format.try_into().map(Self::Raster)
     }
 }
 
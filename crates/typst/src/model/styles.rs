@@ -421,6 +421,15 @@ impl<'a> StyleChain<'a> {
             })
     }
 
+    /// The span of the set rule that the innermost value for the given
+    /// property stems from, if any.
+    pub fn property_span(self, func: ElemFunc, name: &str) -> Option<Span> {
+        self.entries()
+            .filter_map(Style::property)
+            .find(|property| property.is(func, name))
+            .and_then(|property| property.span)
+    }
+
     /// Convert to a style map.
     pub fn to_map(self) -> Styles {
         let mut suffix = Styles::new();
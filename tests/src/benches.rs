@@ -19,6 +19,7 @@ main!(
     bench_eval,
     bench_typeset,
     bench_compile,
+    bench_recompile,
     bench_render,
 );
 
@@ -86,6 +87,14 @@ fn bench_compile(iai: &mut Iai) {
     iai.run(|| typst::compile(&world, &mut tracer));
 }
 
+fn bench_recompile(iai: &mut Iai) {
+    let mut world = BenchWorld::new();
+    let mut tracer = Tracer::default();
+    typst::compile(&world, &mut tracer).unwrap();
+    world.source.edit(1168..1171, "_Uhr_");
+    iai.run(|| typst::compile(&world, &mut tracer));
+}
+
 fn bench_render(iai: &mut Iai) {
     let world = BenchWorld::new();
     let mut tracer = Tracer::default();
@@ -0,0 +1,38 @@
+// Test the baseline grid.
+// Ref: false
+
+---
+#set page(columns: 2, height: 120pt, baseline-grid: 12pt)
+#set par(leading: 12pt - 0.7em)
+= Heading
+#lorem(20)
+
+$ sum_(k=1)^n k = (n(n+1)) / 2 $
+#lorem(20)
+
+---
+// Floats are rounded up to the grid.
+#set page(height: 120pt, baseline-grid: 12pt)
+#place(top, float: true, rect(height: 17pt))
+#lorem(20)
+
+---
+// Headings and odd block spacing stay on the grid.
+#set page(height: 120pt, baseline-grid: 12pt)
+#set par(leading: 12pt - 0.7em)
+#set block(spacing: 7pt)
+= Heading
+#lorem(8)
+#block(stroke: 0.5pt, inset: 3pt)[Boxed text]
+#lorem(8)
+
+---
+// The grid spacing doesn't split lines that avoid an orphan.
+#set page(height: 96pt, baseline-grid: 12pt)
+#set par(leading: 12pt - 0.7em)
+#v(53pt)
+#lorem(20)
+
+---
+// Error: 26-32 expected length or none, found string
+#set page(baseline-grid: "12pt")
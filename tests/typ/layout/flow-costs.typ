@@ -0,0 +1,29 @@
+// Test that the widow and orphan costs are weighed against empty space.
+
+---
+// Free widows and orphans: the paragraph just breaks where the page ends.
+#set page("a8", height: 100pt)
+#set par(costs: (widow: 0%, orphan: 0%))
+#v(42pt)
+#lorem(24)
+
+---
+// A cheap orphan is accepted rather than leaving a third of the page empty.
+#set page("a8", height: 100pt)
+#set par(costs: (orphan: 20%), orphans: 3)
+#v(50pt)
+#lorem(24)
+
+---
+// A widow costs more than leaving a third of the page empty.
+#set page("a8", height: 100pt)
+#set par(costs: (widow: 40%), widows: 3)
+#v(36pt)
+#lorem(20)
+
+---
+// A longer orphan requirement moves more lines.
+#set page("a8", height: 100pt)
+#set par(orphans: 3)
+#v(50pt)
+#lorem(24)
@@ -0,0 +1,24 @@
+// Test that widows and orphans are prevented in grid cells and fixed-height
+// columns, whose paragraphs are laid out by nested flows.
+// Ref: false
+
+---
+// The last two lines of the cell move to the next page together.
+#let pages = state("pages", ())
+#let mark = locate(loc => pages.update(it => it + (loc.page(),)))
+#set page(width: 80pt, height: 55pt, margin: 5pt)
+#grid(columns: 1fr)[#mark A \ #mark B \ #mark C \ #mark D]
+#locate(loc => test(pages.final(loc), (1, 1, 2, 2)))
+
+---
+// The last two lines move to the second column together.
+#let xs = state("xs", ())
+#let mark = locate(loc => xs.update(it => it + (loc.position().x,)))
+#set page(width: 120pt, height: auto, margin: 5pt)
+#block(height: 45pt, columns(2)[#mark A \ #mark B \ #mark C \ #mark D])
+#locate(loc => {
+  let (a, b, c, d) = xs.final(loc)
+  test(a, b)
+  test(c, d)
+  test(b < c, true)
+})
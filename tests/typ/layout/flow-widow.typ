@@ -0,0 +1,21 @@
+// Test that widows and orphans are also prevented in nested flows, where
+// the lines of a paragraph are no longer merged into one frame.
+
+---
+// In columns, the last two lines move to the second column together.
+#set page("a8", height: 90pt)
+#show: columns.with(2, gutter: 8pt)
+#lorem(11)
+
+---
+// In a breakable block, the first two lines move to the next page together.
+#set page("a8", height: 100pt)
+#v(58pt)
+#block(breakable: true, stroke: 0.5pt, inset: 2pt, lorem(24))
+
+---
+// A paragraph that is too short for both requirements is kept together.
+#set page("a8", height: 80pt)
+#set par(orphans: 2, widows: 2)
+#v(44pt)
+#block(breakable: true, fill: luma(230), lorem(8))
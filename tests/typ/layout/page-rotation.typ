@@ -0,0 +1,22 @@
+// Test rotated pages.
+
+---
+#set page(width: 100pt, height: 140pt)
+Portrait.
+#page(rotation: 90deg)[Landscape.]
+#page(rotation: -180deg)[Upside down.]
+Portrait again.
+
+---
+// Links and outline entries point into rotated pages.
+#set page(width: 100pt, height: 140pt)
+#link(<turned>)[Go to the rotated page.]
+#page(rotation: 270deg)[
+  = Turned <turned>
+  #link(<start>)[Back]
+]
+= Start <start>
+
+---
+// Error: 2-26 page rotation must be a multiple of 90deg
+#page(rotation: 45deg)[A]
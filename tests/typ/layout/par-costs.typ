@@ -0,0 +1,34 @@
+// Test paragraph costs, looseness and adjustment limits.
+// Ref: false
+
+---
+#set par(costs: (hyphenation: 200%, runt: 0%))
+#set par(costs: (widow: 0%, orphan: 0%))
+#set par(looseness: 1, max-shrink: 50%, max-stretch: 150%)
+
+---
+// Error: 17-31 unexpected key "hyphen", valid keys are "hyphenation", "runt", "widow", and "orphan"
+#set par(costs: (hyphen: 100%))
+
+---
+// Error: 17-36 expected ratio, found string
+#set par(costs: (hyphenation: "50"))
+
+---
+// Error: 17-29 costs must not be negative
+#set par(costs: (runt: -50%))
+
+---
+// Error: 22-26 ratio must not be negative
+#set par(max-shrink: -10%)
+
+---
+// Error: 23-26 ratio must not be negative
+#set par(max-stretch: -1%)
+
+---
+#set par(orphans: 3, widows: 1)
+
+---
+// Error: 19-20 number must be positive
+#set par(orphans: 0)
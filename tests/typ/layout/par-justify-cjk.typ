@@ -8,6 +8,7 @@
 #set text(lang: "zh", font: "Noto Serif CJK SC")
 
 #rect(inset: 0pt, width: 80pt, fill: rgb("eee"))[
+  // Warning: 3-120 fell back to font "Noto Serif CJK JP" for the characters "別來"
   中文维基百科使用汉字书写，汉字是汉族或华人的共同文字，是中国大陆、新加坡、马来西亚、台湾、香港、澳门的唯一官方文字或官方文字之一。25.9%，而美国和荷兰则分別占13.7%及8.2%。近年來，中国大陆地区的维基百科编辑者正在迅速增加；
 ]
 
@@ -45,7 +46,9 @@
 #set text(lang: "zh", font: "Noto Serif CJK SC")
 #set par(justify: true)
 
+// Warning: 1-73 fell back to font "Noto Serif CJK JP" for the characters "孚"
 孔雀最早见于《山海经》中的《海内经》：“有孔雀。”东汉杨孚著《异物志》记载，岭南：“孔雀，其大如大雁而足高，毛皆有斑纹彩，捕而蓄之，拍手即舞。”
 
 #set text(lang: "zh", region: "hk", font: "Noto Serif CJK TC")
+// Warning: 1-73 fell back to font "Noto Serif CJK SC" for the characters "见经内东汉杨异记载岭纹"
 孔雀最早见于《山海经》中的《海内经》：「有孔雀。」东汉杨孚著《异物志》记载，岭南：「孔雀，其大如大雁而足高，毛皆有斑纹彩，捕而蓄之，拍手即舞。」
@@ -0,0 +1,15 @@
+// Test kashida justification for Arabic text.
+// Ref: false
+
+---
+#set page(width: 150pt)
+#set par(justify: true)
+#set text(lang: "ar", font: "Noto Sans Arabic")
+لأن الاعتراف بالكرامة المتأصلة في جميع أعضاء الأسرة البشرية وبحقوقهم المتساوية الثابتة هو أساس الحرية والعدل والسلام في العالم.
+
+---
+// Explicitly set script.
+#set page(width: 150pt)
+#set par(justify: true)
+#set text(lang: "ar", script: "arab", font: "Noto Sans Arabic")
+لأن الاعتراف بالكرامة المتأصلة في جميع أعضاء الأسرة البشرية وبحقوقهم المتساوية الثابتة هو أساس الحرية والعدل والسلام في العالم.
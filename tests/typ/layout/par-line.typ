@@ -0,0 +1,35 @@
+// Test paragraph line numbering.
+
+---
+// Ref: false
+#set par.line(numbering: "1", interval: 5, side: "outside", scope: "page")
+#set par.line(numbering: "i", side: left, clearance: 2em)
+
+---
+// Error: 21-24 must be `left`, `right`, `"inside"`, or `"outside"`
+#set par.line(side: top)
+
+---
+// Error: 25-26 number must be positive
+#set par.line(interval: 0)
+
+---
+// Numbers are placed in the margin next to each line.
+#set page(width: 150pt, height: auto, margin: (x: 35pt, y: 10pt))
+#set par.line(numbering: "1")
+#lorem(12)
+
+#set par.line(numbering: "(i)", side: right, interval: 2)
+#lorem(16)
+
+---
+// Outside numbers alternate sides and restart on every page.
+#set page(width: 150pt, height: 80pt, margin: (x: 35pt, y: 10pt))
+#set par.line(numbering: "1", side: "outside", scope: "page")
+#lorem(40)
+
+---
+// Lines in footnote entries are not numbered.
+#set page(width: 150pt, height: 90pt, margin: (x: 35pt, y: 10pt))
+#set par.line(numbering: "1")
+Text with a note#footnote[The note's text.] at the bottom.
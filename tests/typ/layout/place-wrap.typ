@@ -0,0 +1,32 @@
+// Test text wrapping around placed content.
+
+---
+#set page(height: 120pt)
+#place(top + right, wrap: true, rect(width: 30pt, height: 40pt))
+#lorem(30)
+
+---
+// Justified text next to an exclusion uses the optimized line breaker.
+#set page(height: 120pt)
+#set par(justify: true)
+#place(top + left, wrap: true, rect(width: 40pt, height: 50pt))
+#lorem(34)
+
+---
+// Lines of different heights are placed next to the exclusion by their real
+// positions.
+#set page(height: 140pt)
+#place(top + right, wrap: true, rect(width: 30pt, height: 44pt))
+Some text #box(rect(height: 20pt)) with a tall box and $sum_(i=0)^n i$
+in a paragraph that flows around the rectangle and #lorem(14)
+
+---
+// The exclusion doesn't reach into the next page.
+#set page(height: 80pt)
+#v(20pt)
+#place(bottom + right, wrap: true, rect(width: 40pt, height: 40pt))
+#lorem(40)
+
+---
+// Error: 2-44 floating placement cannot wrap text
+#place(top, float: true, wrap: true)[Hello]
@@ -0,0 +1,28 @@
+// Test math layout with other math fonts, fallback glyphs and stylistic sets.
+
+---
+// Stretched delimiters, roots and accents with another math font.
+#show math.equation: set text(font: "Fira Math")
+$ lr(( frac(1, 2) )) sqrt(x / y) vec(1, 2, 3) hat(x y z) $
+
+---
+// Glyphs the math font lacks are borrowed from other fonts.
+#show math.equation: set text(font: ("New Computer Modern Math", "Noto Sans Thai"))
+$ accent(x, ก) quad 1 + ๑๒ $
+
+---
+// Stylistic sets and character variants select alternative glyphs.
+$ cal(A) cal(P) $
+#set text(features: ("ss01",))
+$ cal(A) cal(P) $
+#set text(features: (cv01: 2))
+$ nothing $
+
+---
+// The value of an alternate substitution feature is the zero-based index of
+// the alternate.
+$ 1 + Gamma $
+#set text(features: (aalt: 0))
+$ 1 + Gamma $
+#set text(features: (aalt: 1))
+$ 1 + Gamma $
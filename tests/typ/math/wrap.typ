@@ -0,0 +1,30 @@
+// Test line breaks in inline and block equations.
+
+---
+// Inline equations break after relations and binary operators if enabled.
+#set page(width: 150pt)
+Hence $a + b + c + d + e + f + g + h = i + j + k + l + m + n$ holds.
+
+#set math.equation(wrap: true)
+Hence $a + b + c + d + e + f + g + h = i + j + k + l + m + n$ holds.
+
+---
+// Consecutive relations stay together.
+#set page(width: 120pt)
+#set math.equation(wrap: true)
+We have $x_1 + x_2 <= y <= z + w + v + u$ here.
+
+---
+// Block equations only wrap if enabled.
+#set page(width: 150pt)
+$ (a + b)^4 = a^4 + 4 a^3 b + 6 a^2 b^2 + 4 a b^3 + b^4 $
+
+#set math.equation(wrap: true, wrap-indent: 1em, numbering: "(1)")
+$ (a + b)^4 = a^4 + 4 a^3 b + 6 a^2 b^2 + 4 a b^3 + b^4 $
+
+---
+// Manual line breaks disable wrapping.
+#set page(width: 150pt)
+#set math.equation(wrap: true)
+$ a &= b + c + d + e + f + g + h + i \
+    &= j $
@@ -0,0 +1,18 @@
+// Test endnotes and footnote placement.
+// Ref: false
+
+---
+#set page(height: 120pt)
+#set footnote(placement: "end")
+= First
+A#footnote[One] B#footnote[Two]
+#footnote(placement: "page")[Bottom]
+#endnotes()
+
+= Second
+C#footnote[Three]
+#endnotes()
+
+---
+// Error: 26-33 expected "page" or "end"
+#set footnote(placement: "aside")
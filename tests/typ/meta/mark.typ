@@ -0,0 +1,52 @@
+// Test marks for running headers.
+// Ref: false
+
+---
+#set page(
+  height: 100pt,
+  header: [
+    #pagemark("section", position: "top") |
+    #pagemark("section") |
+    #pagemark("section", position: "last")
+  ],
+)
+#show heading: it => {
+  mark("section", it.body)
+  it
+}
+
+= Introduction
+#lorem(20)
+
+= Methods
+#lorem(10)
+
+= Results
+#lorem(30)
+
+---
+// Each page's header shows the top, first and last mark, falling back to the
+// top mark on pages without marks.
+#let seen = state("seen", ())
+#let entry(name) = seen.update(it => it + (name,))
+#set page(height: 80pt, header: {
+  pagemark("section", position: "top")
+  pagemark("section")
+  pagemark("section", position: "last")
+})
+
+#mark("section", entry("A")) A
+#mark("section", entry("B")) B
+#pagebreak()
+No marks.
+#pagebreak()
+#mark("section", entry("C")) C
+#locate(loc => test(seen.final(loc), ("A", "B", "B", "B", "B", "B", "C", "C")))
+
+---
+// Outside of headers and footers, page marks display nothing.
+#pagemark("section")
+
+---
+// Error: 32-40 expected "first", "last", or "top"
+#pagemark("section", position: "bottom")
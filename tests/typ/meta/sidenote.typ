@@ -0,0 +1,33 @@
+// Test side notes.
+
+---
+#set page(height: 100pt, margin: (right: 80pt))
+Text#sidenote[First note.] with notes#sidenote[Second note.]
+#sidenote(numbering: none)[Unnumbered.]
+#counter(sidenote).display()
+
+---
+// Stacking and spilling to the next page.
+#set page(width: 200pt, height: 80pt, margin: (x: 70pt), binding: right)
+#for i in range(6) [
+  Line #i#sidenote[A longer note number #i.] \
+]
+
+---
+// Notes that don't fit onto the last page continue on an extra page.
+#set page(height: 60pt, margin: (right: 70pt))
+Short#sidenote[#lorem(8)] text#sidenote[#lorem(8)] with#sidenote[#lorem(8)] notes.
+
+---
+// Numbering is read where the note is placed.
+#set page(height: 60pt, margin: (right: 80pt))
+#set sidenote(numbering: "a")
+One#sidenote[Lettered.]
+#set sidenote(numbering: none)
+Two#sidenote[Not counted.]
+#set sidenote(numbering: "a")
+Three#sidenote[Second letter.]
+
+---
+// Error: 26-30 expected length, found boolean
+#set sidenote(clearance: true)
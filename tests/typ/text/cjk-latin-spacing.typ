@@ -0,0 +1,27 @@
+// Test CJK-Latin spacing and punctuation compression.
+
+---
+#set page(width: 80pt)
+#set text(lang: "zh", font: ("Linux Libertine", "Noto Serif CJK SC"))
+第4章介绍Typst和LaTeX的区别。
+
+#set text(cjk-latin-spacing: false)
+第4章介绍Typst和LaTeX的区别。
+
+---
+// Spacing across style boundaries and at line starts and ends.
+#set page(width: 60pt)
+#set text(lang: "ja", font: ("Linux Libertine", "Noto Serif CJK JP"))
+これは*bold*と_emph_の例です。Typst
+
+---
+// Punctuation compression.
+#set text(lang: "zh", font: "Noto Serif CJK SC")
+他说：「你好。」。*（*测试*）*。
+
+#set text(compress-punctuation: false)
+他说：「你好。」。*（*测试*）*。
+
+---
+// Error: 30-35 expected boolean, found string
+#set text(cjk-latin-spacing: "yes")
@@ -0,0 +1,13 @@
+// Test color glyphs from a font's COLR table.
+
+---
+// The layers are filled with the colors from the font's palette.
+#set text(font: "COLRv0 Test Glyphs", size: 40pt)
+\u{F0E00}
+
+---
+// Color glyphs are positioned like the surrounding text and keep their colors
+// in colored and transformed text.
+#set text(size: 20pt, fill: eastern)
+A#text(font: "COLRv0 Test Glyphs")[\u{F0E00}]B
+#rotate(20deg, text(font: "COLRv0 Test Glyphs")[\u{F0E00}])
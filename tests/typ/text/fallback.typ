@@ -17,4 +17,5 @@ Aب😀🏞سمB
 01️⃣2
 
 // Tofus are rendered with the first font.
+// Warning: 1-6 no font contains the characters "ዲሞ"
 A🐈ዲሞB
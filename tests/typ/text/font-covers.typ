@@ -0,0 +1,25 @@
+// Test fallback warnings and per-script font families.
+// Ref: false
+
+---
+// Warn about fallback beyond a configured font list.
+#set text(font: "PT Sans")
+// Warning: 1-4 fell back to font "Noto Sans Arabic" for the characters "عرب"
+عرب Latin
+
+---
+// Use fonts only for the scripts they cover.
+#set text(font: (
+  (name: "Noto Sans Arabic", covers: "arab"),
+  (name: "Noto Serif Hebrew", covers: "U+0590-05FF"),
+  "PT Sans",
+))
+Latin عربي, עברית.
+
+---
+// Error: 17-58 expected ISO 15924 script code or unicode range
+#set text(font: (name: "PT Sans", covers: "not a script"))
+
+---
+// Error: 17-57 invalid unicode range
+#set text(font: (name: "PT Sans", covers: "U+05FF-0590"))
@@ -0,0 +1,30 @@
+// Test variation axes of variable fonts.
+
+---
+// Named instances are selected by weight and other weights are interpolated.
+#set text(font: "Inter Variable")
+#text(weight: "thin")[Thin]
+#text(weight: "regular")[Regular]
+#text(weight: 550)[Medium-ish]
+#text(weight: "black")[Black]
+
+---
+// The optical size only changes when set explicitly.
+#set text(font: "Inter Variable")
+#text(size: 28pt)[Display] \
+#text(size: 28pt, variations: (opsz: 14))[Text]
+
+---
+// Explicit values override the weight and are clamped to the axis range.
+#set text(font: "Inter Variable", weight: "bold")
+#text(variations: (wght: 250))[Light] \
+#text(variations: (wght: 2000))[Heaviest]
+
+---
+// Fonts without the axes ignore them.
+#set text(variations: (wght: 650, opsz: 12.5, GRAD: -50))
+Static
+
+---
+// Error: 23-36 expected four-letter axis tag
+#set text(variations: (weight: 650))
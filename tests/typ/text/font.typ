@@ -33,6 +33,8 @@ Emoji: 🐪, 🌋, 🏞
 // Disable font fallback beyond the user-specified list.
 // Without disabling, New Computer Modern Math would come to the rescue.
 #set text(font: ("PT Sans", "Twitter Color Emoji"), fallback: false)
+// Warning: 6-7 no font contains the characters "𝛼"
+// Warning: 10-12 no font contains the characters "𝛽"
 2π = 𝛼 + 𝛽. ✅
 
 ---
@@ -0,0 +1,20 @@
+// Test text highlighting.
+// Ref: false
+
+---
+#set page(width: 120pt)
+This is #highlight[important] and #highlight(fill: aqua)[this too].
+
+---
+// Test highlighting across line and page breaks.
+#set page(width: 80pt, height: 40pt)
+#highlight(extent: 1pt)[A highlight that spans multiple lines and pages.]
+
+---
+// Test custom edges.
+#set highlight(top-edge: "bounds", bottom-edge: "baseline")
+#highlight[Typst] #highlight(top-edge: 1em, bottom-edge: -0.2em)[Typst]
+
+---
+// Error: 25-33 expected "baseline", "descender", "bounds", or length
+#highlight(bottom-edge: "middle")[Typst]
@@ -0,0 +1,35 @@
+// Test user-provided hyphenation patterns and exceptions.
+// Ref: false
+
+---
+// Patterns for a language without built-in patterns.
+#set page(width: 40pt)
+#set par(justify: true)
+#set text(
+  lang: "tlh",
+  hyphenation-patterns: (tlh: "/files/hyphenation.tex"),
+)
+Typography and typesetting.
+
+---
+// Exceptions take precedence over the built-in patterns.
+#set page(width: 60pt)
+#set par(justify: true)
+#set text(hyphenation-exceptions: (en: ("data-base", "Typst")))
+A database written in Typst.
+
+---
+// Error: 33-59 file not found (searched at files/missing.tex)
+#set text(hyphenation-patterns: (en: "/files/missing.tex"))
+
+---
+// Error: 33-55 failed to parse hyphenation patterns `/files/bad.txt`: file is not valid utf-8
+#set text(hyphenation-patterns: (en: "/files/bad.txt"))
+
+---
+// Error: 33-68 expected two or three letter language code (ISO 639-1/2/3)
+#set text(hyphenation-patterns: (english: "/files/hyphenation.tex"))
+
+---
+// Error: 35-52 expected array, found string
+#set text(hyphenation-exceptions: (en: "data-base"))
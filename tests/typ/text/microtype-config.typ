@@ -0,0 +1,24 @@
+// Test configuration of protrusion and font expansion.
+// Ref: false
+
+---
+#set par(justify: true)
+#set text(protrusion: ("-": 100%, "“": 40%), expansion: 2%)
+#set text(protrusion: (",": 0%))
+
+---
+// Settings for individual fonts.
+#set text(protrusion: ("Linux Libertine": ("-": 80%), ".": 50%))
+#set text(expansion: ("linux libertine": 3%, "IBM Plex Sans": 1%))
+
+---
+// Error: 23-35 expected single characters or font families as keys
+#set text(protrusion: ("--": 100%))
+
+---
+// Error: 23-33 expected ratio, found length
+#set text(protrusion: ("-": 1pt))
+
+---
+// Error: 22-25 expected ratio or dictionary, found length
+#set text(expansion: 1pt)
@@ -21,3 +21,27 @@
 #set align(end)
 #set text(dir: rtl)
 :
+
+---
+// Punctuation at the end of a line hangs into the margin. Configured
+// characters also hang in at the start of a line.
+#set page(width: 140pt)
+#set par(justify: true)
+#set text(protrusion: ("“": 50%))
+“This justified paragraph ends lines with commas, periods, and hy-phens.
+Only the configured opening quote hangs in at the start.”
+
+---
+// Expansion only applies to the listed font.
+#set page(width: 140pt)
+#set par(justify: true)
+#set text(expansion: ("linux libertine": 3%))
+This text in Linux Libertine is expanded to even out its spacing.
+#text(font: "IBM Plex Sans")[This text is not expanded at all.]
+
+---
+// Right-to-left text still hangs its punctuation into the left margin.
+#set page(width: 120pt)
+#set par(justify: true)
+#set text(lang: "ar", font: ("Noto Sans Arabic", "Linux Libertine"))
+هذه فقرة عربية قصيرة، تنتهي أسطرها بعلامات الترقيم، مثل الفاصلة والنقطة.
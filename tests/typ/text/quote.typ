@@ -0,0 +1,31 @@
+// Test the quote element.
+// Ref: false
+
+---
+// Test language-aware inline quotes with nesting.
+#quote[She said #quote[Hello] and left.]
+
+#set text(lang: "de")
+#quote[Er sagte #quote[Hallo] und ging.]
+
+#set text(lang: "fr")
+#quote[Il a dit #quote[Bonjour].]
+
+---
+// Test block quotes with and without quotation marks.
+#set page(width: 150pt)
+#quote(block: true, attribution: [Plato])[
+  I seem, then, in just this little thing to be wiser than this man.
+]
+#quote(block: true, quotes: true)[Ich bin ein Berliner.]
+#quote(quotes: false)[Not quoted.]
+
+---
+// Test attributions from bibliography entries.
+#quote(attribution: <tolkien54>)[You cannot pass.]
+#quote(block: true, attribution: <tolkien54>)[You cannot pass.]
+#bibliography("/files/works.bib")
+
+---
+// Error: 21-23 expected label, content, or none, found integer
+#quote(attribution: 12)[Text]
@@ -0,0 +1,31 @@
+// Test ruby annotations.
+// Ref: false
+
+---
+#set page(width: 120pt)
+#set text(font: "Noto Serif CJK JP", lang: "ja")
+#ruby[東京][とうきょう]に住む。
+#ruby(mode: "mono")[漢字][かん|じ]を書く。
+#ruby(mode: "jukugo")[明日][あ|した]と
+#ruby(mode: "jukugo")[今日][きょう]。
+
+---
+// Overhang and gap.
+#set text(font: "Noto Serif CJK JP")
+#ruby(overhang: 0pt, gap: 2pt)[嘘][うそ]だ
+
+---
+// Annotations only overhang adjacent text. Next to each other and at the
+// start of a line, the bases are widened instead.
+#set page(width: 80pt)
+#set text(font: "Noto Serif CJK JP")
+だ#ruby(mode: "mono")[東京][とう|きょう]だ \
+#ruby[東][とうきょう]だ
+
+---
+// Error: 2-31 ruby annotation has 3 parts, but its base has 2 characters
+#ruby(mode: "mono")[漢字][か|ん|じ]
+
+---
+// Error: 2-32 mono ruby requires plain text
+#ruby(mode: "mono")[*漢字*][かん|じ]
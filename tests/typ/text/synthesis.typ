@@ -0,0 +1,16 @@
+// Test synthesized bold, italics and small capitals.
+// Ref: false
+
+---
+// Ubuntu has no bold or italic face and no small capitals.
+#set text(font: "Ubuntu")
+_Slanted_ *bold* #smallcaps[Small Caps] \
+#underline(smallcaps[Underlined])
+
+---
+#set text(font: "Ubuntu", synthesis: (bold: false))
+*Regular*
+
+---
+// Error: 22-36 unexpected key "weight", valid keys are "bold", "italic", and "smallcaps"
+#set text(synthesis: (weight: true))
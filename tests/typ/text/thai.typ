@@ -0,0 +1,15 @@
+// Test line breaking and justification of Thai text.
+
+---
+// Thai has no spaces between words, so lines must break at word boundaries
+// found by the segmenter.
+#set page(width: 100pt)
+#set text(font: "Noto Sans Thai", lang: "th")
+ภาษาไทยเป็นภาษาที่มีระดับเสียงของคำแน่นอนหรือวรรณยุกต์เช่นเดียวกับภาษาจีน
+
+---
+// Justified lines are stretched between clusters.
+#set page(width: 100pt)
+#set par(justify: true)
+#set text(font: "Noto Sans Thai", lang: "th")
+ภาษาไทยเป็นภาษาที่มีระดับเสียงของคำแน่นอนหรือวรรณยุกต์เช่นเดียวกับภาษาจีน
@@ -0,0 +1,40 @@
+// Test vertical text.
+// Ref: false
+
+---
+#set page(width: 120pt, height: 100pt)
+#set text(dir: ttb, lang: "ja", font: "Noto Serif CJK SC")
+// Warning: 1-34 fell back to font "Noto Serif CJK JP" for the characters "吾輩無見組"
+吾輩は猫である。名前はまだ無い。どこで生れたかとんと見当がつかぬ。
+Typstで組版する。
+
+---
+// Lines that don't fit the region's width continue in the next frame.
+#set page(width: 40pt, height: 60pt)
+#set text(dir: ttb, font: "Noto Serif CJK SC")
+// Warning: 1-25 fell back to font "Noto Serif CJK JP" for the characters "覺曉處聞鳥來風聲"
+春眠不覺曉，處處聞啼鳥。夜來風雨聲，花落知多少。
+
+
+---
+// Start and end refer to the inline axis, so consecutive paragraphs are
+// indented and end-aligned lines move to the bottom.
+#set page(width: 100pt, height: 80pt)
+#set par(first-line-indent: 1em)
+#set text(dir: ttb, font: "Noto Serif CJK SC")
+// Warning: 1-13 fell back to font "Noto Serif CJK JP" for the characters "覺曉處聞鳥"
+春眠不覺曉，處處聞啼鳥。
+
+// Warning: 1-13 fell back to font "Noto Serif CJK JP" for the characters "來風聲"
+夜來風雨聲，花落知多少。
+// Warning: 13-14 fell back to font "Noto Serif CJK JP" for the characters "終"
+#align(end)[終]
+
+---
+// In a region of infinite height, vertical text is as high as its longest
+// line.
+#set page(width: 100pt, height: auto)
+#set text(dir: ttb, font: "Noto Serif CJK SC")
+// Warning: 1-13 fell back to font "Noto Serif CJK JP" for the characters "覺曉處聞鳥來風聲"
+春眠不覺曉，處處聞啼鳥。 \
+夜來風雨聲。